@@ -1,26 +1,320 @@
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::{piece::Piece, Color, Move, Position, Square};
+use crate::{
+    fen,
+    opening::Book,
+    piece::Piece,
+    tt::{TranspositionTable, TtEntry, DEFAULT_CAPACITY},
+    zobrist, CastlingRights, Color, Move, Position, Square,
+};
 
-const MAX_DEPTH: usize = 4;
+pub(crate) const MAX_DEPTH: usize = 4;
+/// Depth cap for timed searches, which otherwise deepen until time runs out.
+const MAX_TIMED_DEPTH: usize = 64;
+/// Bound for search scores; comfortably away from `i32` limits so it can be
+/// negated freely.
+const INFINITY: i32 = 1_000_000;
+/// Score for delivering mate at the root. A mate found `ply` plies in scores
+/// `MATE - ply`, so quicker mates score higher.
+pub(crate) const MATE: i32 = 100_000;
+/// Scores beyond this are mate scores.
+pub(crate) const MATE_THRESHOLD: i32 = MATE - 1_000;
+/// Bonus for holding both bishops.
+const BISHOP_PAIR_BONUS: i32 = 5;
+/// Penalty for each file holding more than one pawn of the same color.
+const DOUBLED_PAWN_PENALTY: i32 = 5;
+/// Penalty for each pawn with no friendly pawns on the neighboring files.
+const ISOLATED_PAWN_PENALTY: i32 = 3;
+/// Bonus for a passed pawn by how many ranks it has advanced.
+const PASSED_PAWN_BONUS: [i32; 8] = [0, 2, 4, 7, 12, 20, 30, 0];
+/// Bonus for each friendly pawn on the king's file or a neighboring one,
+/// by whether it stands one or two ranks in front of the king.
+const PAWN_SHIELD_BONUS: [i32; 2] = [10, 5];
+/// Penalty for each file next to or under the king with no friendly pawn.
+const OPEN_FILE_NEAR_KING_PENALTY: i32 = 10;
+/// Penalty for a knight or bishop with no safe square to move to.
+const TRAPPED_PIECE_PENALTY: i32 = 15;
+/// Bonus for a rook on a file with no pawns.
+const ROOK_OPEN_FILE_BONUS: i32 = 10;
+/// Bonus for a rook on a file with only enemy pawns.
+const ROOK_SEMI_OPEN_FILE_BONUS: i32 = 5;
+/// How much shallower the search after a null move goes.
+const NULL_MOVE_REDUCTION: usize = 2;
+/// Moves searched at full depth before late move reductions kick in.
+const LMR_FULL_DEPTH_MOVES: usize = 3;
+/// Shallowest depth at which late moves are reduced.
+const LMR_MIN_DEPTH: usize = 3;
+/// Deepest the quiescence search goes below the main search's leaves.
+const MAX_QUIESCENCE_PLY: usize = 8;
+/// Slack for positional gains when deciding a capture can't raise alpha.
+const DELTA_MARGIN: i32 = 20;
+/// Initial half-width of the aspiration window around the previous score.
+const ASPIRATION_WINDOW: i32 = 20;
+const PROMOTION_PIECES: [Piece; 4] = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveError {
+    /// There is no piece on the source square.
+    EmptySquare(Position),
+    /// The piece on the source square belongs to the side not to move.
+    WrongColor(Position),
+    /// The move is not legal in the current position.
+    IllegalMove(Move),
+    /// The move starts or ends off the board.
+    OffBoard(Position),
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::EmptySquare(pos) => {
+                write!(f, "no piece on square ({}, {})", pos.row, pos.col)
+            }
+            MoveError::WrongColor(pos) => write!(
+                f,
+                "piece on square ({}, {}) does not belong to the side to move",
+                pos.row, pos.col
+            ),
+            MoveError::IllegalMove(mv) => write!(
+                f,
+                "illegal move from ({}, {}) to ({}, {})",
+                mv.from.row, mv.from.col, mv.to.row, mv.to.col
+            ),
+            MoveError::OffBoard(pos) => {
+                write!(f, "square ({}, {}) is off the board", pos.row, pos.col)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    Checkmate { winner: Color },
+    Stalemate,
+    Draw { reason: DrawReason },
+    Ongoing,
+}
+
+/// Why a game ended in a draw other than by stalemate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawReason {
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    InsufficientMaterial,
+}
+
+/// Weights for the evaluation, so the engine's style can be tuned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EvalParams {
+    pub pawn: i32,
+    pub knight: i32,
+    pub bishop: i32,
+    pub rook: i32,
+    pub queen: i32,
+    /// Per pseudo-legal move more than the opponent has.
+    pub mobility_weight: i32,
+    /// Multiplies the pawn shield and open file terms around the kings.
+    pub king_safety_weight: i32,
+}
+
+impl EvalParams {
+    /// The material value of `piece`; kings count for nothing.
+    pub fn piece_value(&self, piece: Piece) -> i32 {
+        match piece {
+            Piece::Pawn => self.pawn,
+            Piece::Knight => self.knight,
+            Piece::Bishop => self.bishop,
+            Piece::Rook => self.rook,
+            Piece::Queen => self.queen,
+            Piece::King | Piece::Empty => 0,
+        }
+    }
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        EvalParams {
+            pawn: Piece::Pawn.value(),
+            knight: Piece::Knight.value(),
+            bishop: Piece::Bishop.value(),
+            rook: Piece::Rook.value(),
+            queen: Piece::Queen.value(),
+            mobility_weight: 1,
+            king_safety_weight: 1,
+        }
+    }
+}
+
+/// Progress report for one completed iteration of the search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchInfo {
+    pub depth: usize,
+    /// Positions visited so far in this search.
+    pub nodes: u64,
+    /// Score from the perspective of the side to move.
+    pub score: i32,
+    pub pv: Vec<Move>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    /// Score of the best move from the perspective of the side to move.
+    pub score: i32,
+    /// Principal variation: the line the search expects, starting with
+    /// `best_move`.
+    pub pv: Vec<Move>,
+}
+
+/// State needed to take back a move with `undo_move`.
+#[derive(Clone, Copy, Debug)]
+struct HistoryEntry {
+    mv: Move,
+    /// Set when the move castled.
+    castled: Option<CastlingSide>,
+    /// The moving piece as it stood before the move (a pawn for promotions).
+    moved: Square,
+    captured: Square,
+    /// Where the captured piece stood; differs from `mv.to` for en passant.
+    captured_pos: Position,
+    castling: CastlingRights,
+    en_passant: Option<Position>,
+    halfmove_clock: usize,
+    zobrist: u64,
+}
+
+/// Which way a king castles.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(crate) enum CastlingSide {
+    Queenside,
+    Kingside,
+}
+
+impl CastlingSide {
+    /// The files the king and the rook end up on, which are the same in
+    /// Chess960 as in standard chess.
+    fn destination_cols(self) -> (usize, usize) {
+        match self {
+            CastlingSide::Queenside => (2, 3),
+            CastlingSide::Kingside => (6, 5),
+        }
+    }
+}
+
+/// Scrambles `mv` with `seed` (SplitMix64) into an arbitrary but
+/// reproducible ordering key.
+fn tie_break_key(seed: u64, mv: &Move) -> u64 {
+    let square = |pos: Position| (pos.row * 8 + pos.col) as u64;
+    let promotion = mv.promotion.map_or(0, |piece| piece as u64 + 1);
+    let mut key = seed ^ (promotion << 12 | square(mv.from) << 6 | square(mv.to));
+    key = (key ^ (key >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    key = (key ^ (key >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    key ^ (key >> 31)
+}
+
+/// Whether signed coordinates, as computed when stepping off a square,
+/// still name a square on the board.
+#[inline]
+fn on_board(i: isize, j: isize) -> bool {
+    (0..8).contains(&i) && (0..8).contains(&j)
+}
+
+fn color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
 
 pub struct Board {
     pub squares: [[Square; 8]; 8],
-    transposition_table: HashMap<u64, i32>,
+    /// Search results keyed by position hash. Not carried over by `clone`.
+    transposition_table: TranspositionTable,
+    /// Prefer `set_turn` to assigning this, which leaves a stale en passant
+    /// square and position hash behind.
     pub turn: Color,
+    /// Square a pawn skipped over with a two-square advance on the last move.
+    pub en_passant: Option<Position>,
+    pub castling: CastlingRights,
+    /// Files the king and the queenside and kingside rooks castle from:
+    /// e, a and h unless the game is Chess960.
+    pub(crate) castling_king_col: usize,
+    pub(crate) castling_rook_cols: [usize; 2],
+    /// Whether castling moves are written as the king taking its own rook,
+    /// as Chess960 needs since the king may move one square or none.
+    pub(crate) chess960: bool,
+    /// Halfmoves since the last capture or pawn advance.
+    pub halfmove_clock: usize,
+    /// Starts at 1 and is incremented after Black's move.
+    pub fullmove_number: usize,
+    history: Vec<HistoryEntry>,
+    /// How often each earlier position in the game occurred, keyed by hash.
+    /// The current position is not included.
+    position_counts: HashMap<u64, usize>,
+    /// When set, the search unwinds as soon as this instant passes.
+    deadline: Option<Instant>,
+    /// Raised by a parallel search once its main thread is done, telling
+    /// the helper threads to unwind too.
+    stop: Option<Arc<AtomicBool>>,
+    /// Depth of the last fully completed iteration of the latest search.
+    completed_depth: usize,
+    /// Whether killer moves and the history heuristic order quiet moves.
+    move_heuristics: bool,
+    /// Whether the evaluation counts pseudo-legal moves for mobility. That
+    /// generates moves for both sides at every leaf, so it is costly.
+    mobility_term: bool,
+    eval_params: EvalParams,
+    /// How much the side to move at the root dislikes a draw; positive
+    /// values make it play on in positions it could settle as a draw.
+    contempt: i32,
+    /// Whether the search tries passing the turn to prove a cutoff early.
+    null_move: bool,
+    /// Whether quiet moves late in the ordering are searched shallower first.
+    late_move_reductions: bool,
+    /// Whether each deepening iteration starts with a narrow window around
+    /// the previous iteration's score.
+    aspiration_windows: bool,
+    /// Breaks root ties that `more_criteria` leaves open, so a given seed
+    /// always picks the same move.
+    seed: Option<u64>,
+    /// Consulted by `find_best_move` before searching. Shared between clones.
+    book: Option<Arc<Book>>,
+    /// Null moves made on the way to the current search node.
+    null_plies: usize,
+    /// Up to two quiet moves per ply that recently caused a beta cutoff.
+    killers: Vec<[Option<Move>; 2]>,
+    /// Cutoff counts for quiet moves, weighted by depth.
+    history_scores: HashMap<(Piece, Position), i32>,
+    /// Length of `history` when the current search started.
+    root_ply: usize,
+    /// Positions visited by the latest search, quiescence included.
+    nodes: u64,
+    /// Where each king stands, White first, kept up to date by `make_move`
+    /// and `undo_move`. `find_king` checks it against `squares`, since those
+    /// can be edited directly.
+    kings: [Option<Position>; 2],
+    /// Zobrist hash of the position, kept up to date by `make_move` and
+    /// `undo_move` rather than recomputed at every node.
+    zobrist: u64,
 }
 
 impl Board {
     pub fn new() -> Self {
-        let empty_square = Square::new(Piece::Empty, None);
-        let mut squares = [[empty_square; 8]; 8];
+        let mut board = Board::empty();
+        board.castling = CastlingRights::all();
+        let squares = &mut board.squares;
 
         // Initialize pieces for both players
-        for i in 0..8 {
-            squares[1][i] = Square::new(Piece::Pawn, Some(Color::White));
-            squares[6][i] = Square::new(Piece::Pawn, Some(Color::Black));
-        }
+        squares[1] = [Square::new(Piece::Pawn, Some(Color::White)); 8];
+        squares[6] = [Square::new(Piece::Pawn, Some(Color::Black)); 8];
 
         squares[0][0] = Square::new(Piece::Rook, Some(Color::White));
         squares[0][7] = Square::new(Piece::Rook, Some(Color::White));
@@ -42,113 +336,584 @@ impl Board {
         squares[7][3] = Square::new(Piece::Queen, Some(Color::Black));
         squares[7][4] = Square::new(Piece::King, Some(Color::Black));
 
+        board.locate_kings();
+        board.refresh_hash();
+        board
+    }
+
+    /// The Chess960 starting position numbered `scheme`, using the standard
+    /// numbering in which 518 is the usual setup. Castling moves on the
+    /// board are written as the king taking its own rook, e.g. `e1h1`.
+    ///
+    /// Panics if `scheme` is 960 or more.
+    pub fn from_chess960(scheme: u16) -> Self {
+        assert!(scheme < 960, "Chess960 schemes are numbered 0 to 959");
+        let mut n = scheme as usize;
+        let mut back_rank = [Piece::Empty; 8];
+
+        // One bishop on each color, then the queen and knights on the
+        // squares left over, then rook, king, rook in the last three
+        back_rank[2 * (n % 4) + 1] = Piece::Bishop;
+        n /= 4;
+        back_rank[2 * (n % 4)] = Piece::Bishop;
+        n /= 4;
+        let empty_cols = |back_rank: &[Piece; 8]| -> Vec<usize> {
+            (0..8)
+                .filter(|&col| back_rank[col] == Piece::Empty)
+                .collect()
+        };
+        back_rank[empty_cols(&back_rank)[n % 6]] = Piece::Queen;
+        n /= 6;
+        const KNIGHTS: [(usize, usize); 10] = [
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (2, 3),
+            (2, 4),
+            (3, 4),
+        ];
+        let empty = empty_cols(&back_rank);
+        let (first, second) = KNIGHTS[n];
+        back_rank[empty[first]] = Piece::Knight;
+        back_rank[empty[second]] = Piece::Knight;
+        let empty = empty_cols(&back_rank);
+        for (col, piece) in empty.iter().zip([Piece::Rook, Piece::King, Piece::Rook]) {
+            back_rank[*col] = piece;
+        }
+
+        let mut board = Board::empty();
+        board.castling = CastlingRights::all();
+        board.castling_king_col = empty[1];
+        board.castling_rook_cols = [empty[0], empty[2]];
+        board.chess960 = true;
+        for (col, piece) in back_rank.into_iter().enumerate() {
+            board.squares[0][col] = Square::new(piece, Some(Color::White));
+            board.squares[7][col] = Square::new(piece, Some(Color::Black));
+        }
+        board.squares[1] = [Square::new(Piece::Pawn, Some(Color::White)); 8];
+        board.squares[6] = [Square::new(Piece::Pawn, Some(Color::Black)); 8];
+
+        board.locate_kings();
+        board.refresh_hash();
+        board
+    }
+
+    /// A board with no pieces on it, White to move and no castling rights.
+    pub fn empty() -> Self {
         Board {
-            squares,
-            transposition_table: HashMap::new(),
+            squares: [[Square::new(Piece::Empty, None); 8]; 8],
+            transposition_table: TranspositionTable::new(DEFAULT_CAPACITY),
             turn: Color::White,
+            en_passant: None,
+            castling: CastlingRights::none(),
+            castling_king_col: 4,
+            castling_rook_cols: [0, 7],
+            chess960: false,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            history: Vec::new(),
+            position_counts: HashMap::new(),
+            deadline: None,
+            stop: None,
+            completed_depth: 0,
+            move_heuristics: true,
+            mobility_term: true,
+            eval_params: EvalParams::default(),
+            contempt: 0,
+            null_move: true,
+            late_move_reductions: true,
+            aspiration_windows: true,
+            seed: None,
+            book: None,
+            null_plies: 0,
+            killers: Vec::new(),
+            history_scores: HashMap::new(),
+            root_ply: 0,
+            nodes: 0,
+            kings: [None; 2],
+            zobrist: 0,
+        }
+    }
+
+    /// The same position with the colors swapped: ranks flipped top to
+    /// bottom, each piece changing sides, and the side to move, castling
+    /// rights and en passant square following along. Any sound evaluation
+    /// scores it as the exact opposite of this one. The game history is not
+    /// carried over.
+    pub fn mirror(&self) -> Board {
+        let mut mirrored = Board::empty();
+        for (row, rank) in self.squares.iter().enumerate() {
+            for (col, square) in rank.iter().enumerate() {
+                let color = square.color.map(|color| match color {
+                    Color::White => Color::Black,
+                    Color::Black => Color::White,
+                });
+                mirrored.squares[7 - row][col] = Square::new(square.piece, color);
+            }
+        }
+        mirrored.turn = match self.turn {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        };
+        mirrored.castling = CastlingRights {
+            white_kingside: self.castling.black_kingside,
+            white_queenside: self.castling.black_queenside,
+            black_kingside: self.castling.white_kingside,
+            black_queenside: self.castling.white_queenside,
+        };
+        mirrored.en_passant = self.en_passant.map(|pos| Position {
+            row: 7 - pos.row,
+            col: pos.col,
+        });
+        mirrored.castling_king_col = self.castling_king_col;
+        mirrored.castling_rook_cols = self.castling_rook_cols;
+        mirrored.chess960 = self.chess960;
+        mirrored.halfmove_clock = self.halfmove_clock;
+        mirrored.fullmove_number = self.fullmove_number;
+        mirrored.eval_params = self.eval_params;
+        mirrored.locate_kings();
+        mirrored.refresh_hash();
+        mirrored
+    }
+
+    /// Starts composing a custom position on an empty board.
+    pub fn builder() -> BoardBuilder {
+        BoardBuilder {
+            board: Board::empty(),
+        }
+    }
+
+    /// The pieces of `color` and where they stand, from a1 through h8.
+    pub fn pieces(&self, color: Color) -> impl Iterator<Item = (Position, Piece)> + '_ {
+        self.squares
+            .iter()
+            .enumerate()
+            .flat_map(move |(row, squares)| {
+                squares
+                    .iter()
+                    .enumerate()
+                    .filter(move |(_, square)| square.color == Some(color))
+                    .map(move |(col, square)| (Position { row, col }, square.piece))
+            })
+    }
+
+    /// Moves that don't leave the mover's own king in check.
+    pub fn get_legal_moves(&self, color: Color) -> Vec<Move> {
+        self.legal_moves_iter(color).collect()
+    }
+
+    /// How many legal moves `color` has.
+    pub fn legal_move_count(&self, color: Color) -> usize {
+        self.legal_moves_iter(color).count()
+    }
+
+    /// Whether `color` has any legal move, stopping at the first one found.
+    fn has_legal_move(&self, color: Color) -> bool {
+        self.legal_moves_iter(color).next().is_some()
+    }
+
+    /// Checks the pseudo-legal moves for legality lazily, one at a time.
+    /// Out of check, only king moves, pinned pieces and en passant can
+    /// expose the king, so only those are tried out on the board.
+    fn legal_moves_iter(&self, color: Color) -> impl Iterator<Item = Move> + '_ {
+        // In check, only moves onto these squares can help, king moves and
+        // en passant aside
+        let evasions = self.is_in_check(color).then(|| self.evasion_squares(color));
+        let pinned: Vec<Position> = self
+            .pinned_pieces(color)
+            .into_iter()
+            .map(|(pinned, _)| pinned)
+            .collect();
+        let mut scratch = self.clone();
+        self.get_valid_moves(color).into_iter().filter(move |mv| {
+            let en_passant = mv.piece == Piece::Pawn && Some(mv.to) == self.en_passant;
+            if mv.piece != Piece::King && !en_passant {
+                if evasions
+                    .as_ref()
+                    .is_some_and(|squares| !squares.contains(&mv.to))
+                {
+                    return false;
+                }
+                if !pinned.contains(&mv.from) {
+                    return true;
+                }
+            }
+            scratch.make_move(*mv);
+            let legal = !scratch.is_in_check(color);
+            scratch.undo_move();
+            legal
+        })
+    }
+
+    /// Where a piece other than the king can answer a check on `color`'s
+    /// king: by taking the checking piece or, if it is a slider, by stepping
+    /// in between. Empty in double check, when only the king can move.
+    fn evasion_squares(&self, color: Color) -> Vec<Position> {
+        let Some((row, col)) = self.find_king(color) else {
+            return Vec::new();
+        };
+        let opponent = match color {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        };
+        let king = Position { row, col };
+        let [checker] = self.attackers_of(king, opponent)[..] else {
+            return Vec::new();
+        };
+
+        let mut squares = vec![checker];
+        let slider = matches!(
+            self.squares[checker.row][checker.col].piece,
+            Piece::Rook | Piece::Bishop | Piece::Queen
+        );
+        if slider {
+            let step = |from: usize, to: usize| (to as isize - from as isize).signum();
+            let (di, dj) = (step(king.row, checker.row), step(king.col, checker.col));
+            let (mut row, mut col) = (king.row as isize + di, king.col as isize + dj);
+            while (row as usize, col as usize) != (checker.row, checker.col) {
+                squares.push(Position {
+                    row: row as usize,
+                    col: col as usize,
+                });
+                row += di;
+                col += dj;
+            }
+        }
+        squares
+    }
+
+    /// Legal moves of the piece on `pos`, or none if it isn't the side to
+    /// move's piece.
+    pub fn legal_moves_from(&self, pos: Position) -> Vec<Move> {
+        if !pos.is_valid() || self.squares[pos.row][pos.col].color != Some(self.turn) {
+            return Vec::new();
         }
+        self.get_legal_moves(self.turn)
+            .into_iter()
+            .filter(|mv| mv.from == pos)
+            .collect()
     }
 
+    /// Generates pseudo-legal moves, which may leave the king in check.
     fn get_valid_moves(&self, color: Color) -> Vec<Move> {
         let mut moves = Vec::new();
-        for (i, row) in self.squares.iter().enumerate() {
-            for (j, square) in row.iter().enumerate() {
-                if square.color == Some(color) {
-                    match square.piece {
-                        Piece::Pawn => {
-                            let direction = if color == Color::White { 1 } else { -1 };
-                            let new_i = (i as isize + direction) as usize;
-                            if new_i < 8 && self.squares[new_i][j].piece == Piece::Empty {
+        for (pos, piece) in self.pieces(color) {
+            self.push_piece_moves(color, pos, piece, &mut moves);
+        }
+
+        self.push_castling_moves(color, &mut moves);
+
+        // Best captures first so alpha-beta cuts off early
+        moves.sort_by_key(|mv| Reverse(Self::mvv_lva(mv)));
+
+        moves
+    }
+
+    /// Pushes the pseudo-legal moves of `piece` on `pos`, castling aside.
+    fn push_piece_moves(&self, color: Color, pos: Position, piece: Piece, moves: &mut Vec<Move>) {
+        let (i, j) = (pos.row, pos.col);
+        match piece {
+            Piece::Pawn => {
+                let direction = if color == Color::White { 1 } else { -1 };
+                // Signed, as a pawn on the last rank has nowhere to go
+                let forward_i = i as isize + direction;
+                let forward = on_board(forward_i, j as isize)
+                    .then_some(forward_i as usize)
+                    .filter(|&new_i| self.squares[new_i][j].piece == Piece::Empty);
+                if let Some(new_i) = forward {
+                    Self::push_pawn_move(moves, pos, Position { row: new_i, col: j }, None);
+
+                    // Two-square advance from the starting rank
+                    let start_row = if color == Color::White { 1 } else { 6 };
+                    let double_i = (i as isize + 2 * direction) as usize;
+                    if i == start_row && self.squares[double_i][j].piece == Piece::Empty {
+                        Self::push_pawn_move(
+                            moves,
+                            pos,
+                            Position {
+                                row: double_i,
+                                col: j,
+                            },
+                            None,
+                        );
+                    }
+                }
+
+                // Diagonal captures
+                let capture_i = i as isize + direction;
+                for dj in [-1, 1] {
+                    let capture_j = j as isize + dj;
+                    if !on_board(capture_i, capture_j) {
+                        continue;
+                    }
+                    let target_square = self.squares[capture_i as usize][capture_j as usize];
+                    if target_square.piece != Piece::Empty
+                        && target_square.color.is_some()
+                        && target_square.color != Some(color)
+                    {
+                        Self::push_pawn_move(
+                            moves,
+                            pos,
+                            Position {
+                                row: capture_i as usize,
+                                col: capture_j as usize,
+                            },
+                            Some(target_square.piece),
+                        );
+                    } else if self.en_passant
+                        == Some(Position {
+                            row: capture_i as usize,
+                            col: capture_j as usize,
+                        })
+                        && capture_i == if color == Color::White { 5 } else { 2 }
+                    {
+                        // En passant: the captured pawn sits beside us, not on
+                        // the target square
+                        Self::push_pawn_move(
+                            moves,
+                            pos,
+                            Position {
+                                row: capture_i as usize,
+                                col: capture_j as usize,
+                            },
+                            Some(Piece::Pawn),
+                        );
+                    }
+                }
+            }
+            Piece::Knight | Piece::King => {
+                for offset in piece.directions() {
+                    self.push_step_move(color, pos, piece, offset, moves);
+                }
+            }
+            Piece::Queen | Piece::Rook | Piece::Bishop => {
+                for &(di, dj) in &piece.directions() {
+                    let mut new_i = i as isize;
+                    let mut new_j = j as isize;
+                    loop {
+                        new_i += di;
+                        new_j += dj;
+                        if !on_board(new_i, new_j) {
+                            break;
+                        }
+                        let target_square = self.squares[new_i as usize][new_j as usize];
+                        if target_square.piece == Piece::Empty {
+                            moves.push(Move {
+                                from: pos,
+                                to: Position {
+                                    row: new_i as usize,
+                                    col: new_j as usize,
+                                },
+                                piece,
+                                captured: None,
+                                promotion: None,
+                                score: 0, // Initial score
+                            });
+                        } else {
+                            if target_square.color != Some(color) {
                                 moves.push(Move {
-                                    from: Position { row: i, col: j },
-                                    to: Position { row: new_i, col: j },
-                                    piece: Piece::Pawn,
-                                    captured: None,
+                                    from: pos,
+                                    to: Position {
+                                        row: new_i as usize,
+                                        col: new_j as usize,
+                                    },
+                                    piece,
+                                    captured: Some(target_square.piece),
+                                    promotion: None,
                                     score: 0, // Initial score
                                 });
                             }
+                            break;
                         }
-                        Piece::King
-                        | Piece::Queen
-                        | Piece::Rook
-                        | Piece::Bishop
-                        | Piece::Knight => {
-                            for &(di, dj) in &square.piece.directions() {
-                                let mut new_i = i as isize;
-                                let mut new_j = j as isize;
-                                loop {
-                                    new_i += di;
-                                    new_j += dj;
-                                    if new_i < 0 || new_i >= 8 || new_j < 0 || new_j >= 8 {
-                                        break;
-                                    }
-                                    let target_square =
-                                        self.squares[new_i as usize][new_j as usize];
-                                    if target_square.piece == Piece::Empty {
-                                        moves.push(Move {
-                                            from: Position { row: i, col: j },
-                                            to: Position {
-                                                row: new_i as usize,
-                                                col: new_j as usize,
-                                            },
-                                            piece: square.piece,
-                                            captured: None,
-                                            score: 0, // Initial score
-                                        });
-                                        if square.piece == Piece::King
-                                            || square.piece == Piece::Knight
-                                        {
-                                            break; // King and Knight move only one step
-                                        }
-                                    } else {
-                                        if target_square.color != Some(color) {
-                                            moves.push(Move {
-                                                from: Position { row: i, col: j },
-                                                to: Position {
-                                                    row: new_i as usize,
-                                                    col: new_j as usize,
-                                                },
-                                                piece: square.piece,
-                                                captured: Some(target_square.piece),
-                                                score: 0, // Initial score
-                                            });
-                                        }
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                        Piece::Empty => {}
                     }
                 }
             }
+            Piece::Empty => {}
+        }
+    }
+
+    /// Most valuable victim, least valuable aggressor. Quiet moves rank last.
+    fn mvv_lva(mv: &Move) -> i32 {
+        match mv.captured {
+            Some(victim) => victim.value() * 10 - mv.piece.value(),
+            None => i32::MIN,
         }
+    }
 
-        // Sort moves based on a heuristic (e.g., captures and checks first)
-        moves.sort_by_key(|mv| match mv.captured {
-            Some(piece) => piece.value(), // Higher value pieces first
-            None => 0,
+    /// Pushes the move one `offset` away from `from`, unless that leaves the
+    /// board or lands on a piece of our own.
+    fn push_step_move(
+        &self,
+        color: Color,
+        from: Position,
+        piece: Piece,
+        (di, dj): (isize, isize),
+        moves: &mut Vec<Move>,
+    ) {
+        let row = from.row as isize + di;
+        let col = from.col as isize + dj;
+        if !on_board(row, col) {
+            return;
+        }
+        let target_square = self.squares[row as usize][col as usize];
+        if target_square.color == Some(color) {
+            return;
+        }
+        moves.push(Move {
+            from,
+            to: Position {
+                row: row as usize,
+                col: col as usize,
+            },
+            piece,
+            captured: (target_square.piece != Piece::Empty).then_some(target_square.piece),
+            promotion: None,
+            score: 0, // Initial score
         });
+    }
 
-        moves
+    /// Pushes a pawn move, expanding it into one move per promotion piece
+    /// when the pawn reaches the last rank.
+    fn push_pawn_move(
+        moves: &mut Vec<Move>,
+        from: Position,
+        to: Position,
+        captured: Option<Piece>,
+    ) {
+        if to.row == 0 || to.row == 7 {
+            for promotion in PROMOTION_PIECES {
+                moves.push(Move {
+                    from,
+                    to,
+                    piece: Piece::Pawn,
+                    captured,
+                    promotion: Some(promotion),
+                    score: 0, // Initial score
+                });
+            }
+        } else {
+            moves.push(Move {
+                from,
+                to,
+                piece: Piece::Pawn,
+                captured,
+                promotion: None,
+                score: 0, // Initial score
+            });
+        }
+    }
+
+    fn push_castling_moves(&self, color: Color, moves: &mut Vec<Move>) {
+        let (row, kingside, queenside) = match color {
+            Color::White => (
+                0,
+                self.castling.white_kingside,
+                self.castling.white_queenside,
+            ),
+            Color::Black => (
+                7,
+                self.castling.black_kingside,
+                self.castling.black_queenside,
+            ),
+        };
+        if !kingside && !queenside {
+            return;
+        }
+        let king_col = self.castling_king_col;
+        if self.squares[row][king_col] != Square::new(Piece::King, Some(color))
+            || self.is_in_check(color)
+        {
+            return;
+        }
+
+        let rook = Square::new(Piece::Rook, Some(color));
+        let is_empty = |col: usize| self.squares[row][col].piece == Piece::Empty;
+        let opponent = match color {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        };
+        let is_safe = |col: usize| !self.is_square_attacked(Position { row, col }, opponent);
+        let span = |a: usize, b: usize| a.min(b)..=a.max(b);
+
+        for (side, allowed) in [
+            (CastlingSide::Kingside, kingside),
+            (CastlingSide::Queenside, queenside),
+        ] {
+            let rook_col = self.castling_rook_cols[side as usize];
+            if !allowed || self.squares[row][rook_col] != rook {
+                continue;
+            }
+            // Everything either piece crosses or lands on must be empty but
+            // for the two of them, and the king may not cross an attack
+            let (king_to, rook_to) = side.destination_cols();
+            let clear = span(king_col, king_to)
+                .chain(span(rook_col, rook_to))
+                .all(|col| col == king_col || col == rook_col || is_empty(col));
+            if clear && span(king_col, king_to).all(|col| col == king_col || is_safe(col)) {
+                let to = if self.chess960 { rook_col } else { king_to };
+                moves.push(Move {
+                    from: Position { row, col: king_col },
+                    to: Position { row, col: to },
+                    piece: Piece::King,
+                    captured: None,
+                    promotion: None,
+                    score: 0, // Initial score
+                });
+            }
+        }
+    }
+
+    /// Which way `mv` castles, if it does. Asks about the position before
+    /// the move is made.
+    pub(crate) fn castling_side(&self, mv: &Move) -> Option<CastlingSide> {
+        let color = self.squares[mv.from.row][mv.from.col].color?;
+        let back_rank = match color {
+            Color::White => 0,
+            Color::Black => 7,
+        };
+        let from_home = mv.from.row == back_rank && mv.from.col == self.castling_king_col;
+        if mv.piece != Piece::King || !from_home || mv.to.row != back_rank {
+            return None;
+        }
+        let onto_own_rook =
+            self.squares[mv.to.row][mv.to.col] == Square::new(Piece::Rook, Some(color));
+        if onto_own_rook || (!self.chess960 && mv.from.col.abs_diff(mv.to.col) == 2) {
+            Some(if mv.to.col > mv.from.col {
+                CastlingSide::Kingside
+            } else {
+                CastlingSide::Queenside
+            })
+        } else {
+            None
+        }
     }
 
+    /// The evaluation from the side to move's point of view, as the
+    /// negamax search wants it. Dead drawn positions score 0 however the
+    /// pieces stand.
+    pub fn evaluate_relative(&self) -> i32 {
+        if self.is_insufficient_material() {
+            return 0;
+        }
+        match self.turn {
+            Color::White => self.evaluate_board(),
+            Color::Black => -self.evaluate_board(),
+        }
+    }
+
+    /// The evaluation from White's point of view.
     fn evaluate_board(&self) -> i32 {
         let mut score = 0;
+        let phase = self.game_phase();
         for (i, row) in self.squares.iter().enumerate() {
             for (j, square) in row.iter().enumerate() {
-                let piece_value = match square.piece {
-                    Piece::King => 900,
-                    Piece::Queen => 90,
-                    Piece::Rook => 50,
-                    Piece::Bishop | Piece::Knight => 30,
-                    Piece::Pawn => 10,
-                    Piece::Empty => 0,
-                };
+                let position_value = Self::piece_square_value(*square, i, j, phase);
 
-                let position_value = square.piece.table()[i][j];
-
-                score += (piece_value + position_value)
+                score += position_value
                     * match square.color {
                         Some(Color::White) => 1,
                         Some(Color::Black) => -1,
@@ -158,57 +923,146 @@ impl Board {
         }
 
         // Add more sophisticated evaluation metrics
-        score += self.evaluate_king_safety();
+        let params = self.eval_params;
+        score += self.material(|piece| params.piece_value(piece));
+        score += params.king_safety_weight * self.evaluate_king_safety();
         score += self.evaluate_pawn_structure();
         score += self.evaluate_piece_activity();
+        score += self.evaluate_rook_files();
+        score += self.evaluate_bishop_pair();
+
+        if self.mobility_term {
+            let white_moves = self.get_valid_moves(Color::White).len() as i32;
+            let black_moves = self.get_valid_moves(Color::Black).len() as i32;
+            score += params.mobility_weight * (white_moves - black_moves);
+        }
+
+        score
+    }
+
+    /// How far the game is from the endgame, from 1.0 with all the
+    /// non-pawn material on the board down to 0.0 with none of it.
+    pub fn game_phase(&self) -> f32 {
+        // Queen, two rooks, two bishops and two knights per side
+        const OPENING_MATERIAL: i32 = 2 * (90 + 2 * 50 + 2 * 30 + 2 * 30);
+        let material: i32 = self
+            .squares
+            .iter()
+            .flatten()
+            .filter(|square| !matches!(square.piece, Piece::King | Piece::Pawn))
+            .map(|square| square.piece.value())
+            .sum();
+        (material as f32 / OPENING_MATERIAL as f32).min(1.0)
+    }
+
+    /// Piece-square bonus for `square` standing on row `i`, column `j`. The
+    /// tables are drawn from White's side with rank 8 on top, so White reads
+    /// them upside down and Black reads them as they are. The king blends its
+    /// middlegame and endgame tables by `phase`.
+    fn piece_square_value(square: Square, i: usize, j: usize, phase: f32) -> i32 {
+        let row = match square.color {
+            Some(Color::White) => 7 - i,
+            Some(Color::Black) => i,
+            None => return 0,
+        };
+        if square.piece == Piece::King {
+            let middlegame = Piece::KING_TABLE[row][j] as f32;
+            let endgame = Piece::KING_ENDGAME_TABLE[row][j] as f32;
+            (middlegame * phase + endgame * (1.0 - phase)).round() as i32
+        } else {
+            square.piece.table()[row][j]
+        }
+    }
 
-        // Add mobility score
-        let white_moves = self.get_valid_moves(Color::White).len() as i32;
-        let black_moves = self.get_valid_moves(Color::Black).len() as i32;
-        score += white_moves - black_moves;
+    /// White's material minus Black's, by `Piece::value`. Both kings are
+    /// always on the board, so the king counts for nothing here; mates are
+    /// scored by the search instead.
+    pub fn material_balance(&self) -> i32 {
+        self.material(|piece| piece.value())
+    }
 
+    /// White's material minus Black's, valuing pieces by `value`.
+    fn material(&self, value: impl Fn(Piece) -> i32) -> i32 {
+        let mut score = 0;
+        for square in self.squares.iter().flatten() {
+            let piece_value = match square.piece {
+                Piece::King | Piece::Empty => 0,
+                piece => value(piece),
+            };
+            score += piece_value
+                * match square.color {
+                    Some(Color::White) => 1,
+                    Some(Color::Black) => -1,
+                    None => 0,
+                };
+        }
         score
     }
 
+    /// Rewards pawns sheltering each king and penalizes open files around
+    /// it. Shelter matters less as pieces come off, so the term fades out
+    /// with the game phase.
     fn evaluate_king_safety(&self) -> i32 {
+        let files = self.pawn_files();
         let mut score = 0;
 
-        for (i, row) in self.squares.iter().enumerate() {
-            for (j, square) in row.iter().enumerate() {
-                if square.piece == Piece::King {
-                    let king_safety_value = if square.color == Some(Color::White) {
-                        // Example heuristic: King safety is better in the corners
-                        if i == 0 && (j == 0 || j == 7) {
-                            20
-                        } else {
-                            10
-                        }
-                    } else {
-                        if i == 7 && (j == 0 || j == 7) {
-                            20
-                        } else {
-                            10
-                        }
-                    };
-                    score += king_safety_value
-                        * match square.color {
-                            Some(Color::White) => 1,
-                            Some(Color::Black) => -1,
-                            None => 0,
-                        };
+        for (color, sign) in [(Color::White, 1), (Color::Black, -1)] {
+            let Some((row, col)) = self.find_king(color) else {
+                continue;
+            };
+            let forward = match color {
+                Color::White => 1,
+                Color::Black => -1,
+            };
+            let pawn = Square::new(Piece::Pawn, Some(color));
+            let own_files = files[color_index(color)].iter().enumerate();
+            for (j, &count) in own_files.take(col + 2).skip(col.saturating_sub(1)) {
+                if count == 0 {
+                    score -= sign * OPEN_FILE_NEAR_KING_PENALTY;
+                    continue;
+                }
+                for (steps, bonus) in (1..).zip(PAWN_SHIELD_BONUS) {
+                    let i = row as isize + steps * forward;
+                    if on_board(i, j as isize) && self.squares[i as usize][j] == pawn {
+                        score += sign * bonus;
+                    }
                 }
             }
         }
 
-        score
+        (score as f32 * self.game_phase()).round() as i32
+    }
+
+    /// Pawns per file, White first.
+    fn pawn_files(&self) -> [[i32; 8]; 2] {
+        let mut files = [[0; 8]; 2];
+        for (color, files) in [Color::White, Color::Black].into_iter().zip(&mut files) {
+            for (pos, piece) in self.pieces(color) {
+                if piece == Piece::Pawn {
+                    files[pos.col] += 1;
+                }
+            }
+        }
+        files
     }
 
     fn evaluate_pawn_structure(&self) -> i32 {
         let mut score = 0;
+        let files = self.pawn_files();
 
-        for (_i, row) in self.squares.iter().enumerate() {
+        for (i, row) in self.squares.iter().enumerate() {
             for (j, square) in row.iter().enumerate() {
                 if square.piece == Piece::Pawn {
+                    if let Some(color) = square.color {
+                        if self.is_passed_pawn(Position { row: i, col: j }, color) {
+                            let advanced = match color {
+                                Color::White => i,
+                                Color::Black => 7 - i,
+                            };
+                            score += PASSED_PAWN_BONUS[advanced]
+                                * if color == Color::White { 1 } else { -1 };
+                        }
+                    }
                     let pawn_structure_value = if square.color == Some(Color::White) {
                         // Example heuristic: Pawns are better in the center
                         if j == 3 || j == 4 {
@@ -233,62 +1087,156 @@ impl Board {
             }
         }
 
+        for (counts, sign) in files.iter().zip([1, -1]) {
+            for (j, &count) in counts.iter().enumerate() {
+                if count > 1 {
+                    score -= sign * DOUBLED_PAWN_PENALTY;
+                }
+                let left = if j > 0 { counts[j - 1] } else { 0 };
+                let right = if j < 7 { counts[j + 1] } else { 0 };
+                if left == 0 && right == 0 {
+                    score -= sign * ISOLATED_PAWN_PENALTY * count;
+                }
+            }
+        }
+
         score
     }
 
+    fn evaluate_bishop_pair(&self) -> i32 {
+        let bishops = |color| {
+            self.squares
+                .iter()
+                .flatten()
+                .filter(|square| square.piece == Piece::Bishop && square.color == Some(color))
+                .count()
+        };
+        let pair = |color| {
+            if bishops(color) >= 2 {
+                BISHOP_PAIR_BONUS
+            } else {
+                0
+            }
+        };
+        pair(Color::White) - pair(Color::Black)
+    }
+
+    /// Whether a `color` pawn on `pos` has no enemy pawns ahead of it on its
+    /// own or the neighboring files.
+    pub fn is_passed_pawn(&self, pos: Position, color: Color) -> bool {
+        let (ahead, enemy) = match color {
+            Color::White => (pos.row + 1..8, Color::Black),
+            Color::Black => (0..pos.row, Color::White),
+        };
+        let files = pos.col.saturating_sub(1)..=(pos.col + 1).min(7);
+        !ahead.into_iter().any(|i| {
+            files.clone().any(|j| {
+                let square = self.squares[i][j];
+                square.piece == Piece::Pawn && square.color == Some(enemy)
+            })
+        })
+    }
+
     fn evaluate_piece_activity(&self) -> i32 {
-        let mut score = 0;
+        let activity = |color| -> i32 {
+            let trapped = self.trapped_minors(color) as i32 * TRAPPED_PIECE_PENALTY;
+            self.pieces(color)
+                .map(|(_, piece)| match piece {
+                    Piece::King => 0, // King activity is not usually considered
+                    Piece::Queen => 10,
+                    Piece::Rook => 5,
+                    Piece::Bishop => 3,
+                    Piece::Knight => 3,
+                    Piece::Pawn => 1,
+                    Piece::Empty => 0,
+                })
+                .sum::<i32>()
+                - trapped
+        };
+        activity(Color::White) - activity(Color::Black)
+    }
 
-        for (_i, row) in self.squares.iter().enumerate() {
-            for (_j, square) in row.iter().enumerate() {
-                if square.piece != Piece::Empty {
-                    let piece_activity_value = match square.piece {
-                        Piece::King => 0, // King activity is not usually considered
-                        Piece::Queen => 10,
-                        Piece::Rook => 5,
-                        Piece::Bishop => 3,
-                        Piece::Knight => 3,
-                        Piece::Pawn => 1,
-                        Piece::Empty => 0,
-                    };
-                    score += piece_activity_value
-                        * match square.color {
-                            Some(Color::White) => 1,
-                            Some(Color::Black) => -1,
-                            None => 0,
-                        };
+    /// Knights and bishops of `color` that can move but have nowhere to go
+    /// without being won, judged by `see`. Pieces that can't move at all are
+    /// blocked rather than trapped, and are left alone.
+    fn trapped_minors(&self, color: Color) -> usize {
+        let mut moves = Vec::new();
+        self.pieces(color)
+            .filter(|(_, piece)| matches!(piece, Piece::Knight | Piece::Bishop))
+            .filter(|&(pos, piece)| {
+                moves.clear();
+                self.push_piece_moves(color, pos, piece, &mut moves);
+                !moves.is_empty() && moves.iter().all(|mv| self.see(mv) < 0)
+            })
+            .count()
+    }
+
+    /// Rewards rooks on open and semi-open files.
+    fn evaluate_rook_files(&self) -> i32 {
+        let files = self.pawn_files();
+        let mut score = 0;
+        for (color, sign) in [(Color::White, 1), (Color::Black, -1)] {
+            let own = files[color_index(color)];
+            let enemy = files[1 - color_index(color)];
+            for (pos, piece) in self.pieces(color) {
+                if piece != Piece::Rook || own[pos.col] > 0 {
+                    continue;
                 }
+                score += sign
+                    * if enemy[pos.col] == 0 {
+                        ROOK_OPEN_FILE_BONUS
+                    } else {
+                        ROOK_SEMI_OPEN_FILE_BONUS
+                    };
             }
         }
-
         score
     }
 
-    fn quiescence_search(&self, mut alpha: i32, beta: i32) -> i32 {
-        let stand_pat = self.evaluate_board();
+    /// Scores are from the perspective of the side to move (negamax).
+    /// `qply` counts the plies since the main search's leaf; past
+    /// `MAX_QUIESCENCE_PLY` the static evaluation stands.
+    fn quiescence_search(&mut self, mut alpha: i32, beta: i32, qply: usize) -> i32 {
+        self.nodes += 1;
+        if self.is_out_of_time() {
+            return 0;
+        }
+
+        let stand_pat = self.evaluate_relative();
         if stand_pat >= beta {
             return beta;
         }
         if alpha < stand_pat {
             alpha = stand_pat;
         }
+        if qply >= MAX_QUIESCENCE_PLY {
+            return alpha;
+        }
 
-        let mut valid_moves = self.get_valid_moves(self.current_turn());
-        valid_moves.retain(|mv| {
-            mv.captured.is_some() || {
-                let target_square = self.squares[mv.to.row][mv.to.col];
-                if let Some(color) = target_square.color {
-                    self.is_in_check(color)
-                } else {
-                    false
-                }
+        let mut valid_moves: Vec<(i32, Move)> = self
+            .quiescence_moves()
+            .into_iter()
+            .map(|mv| (self.see(&mv), mv))
+            .collect();
+        // Most promising exchanges first
+        valid_moves.sort_by_key(|&(see, _)| Reverse(see));
+        for &(see, mv) in &valid_moves {
+            // Captures that lose material to the recaptures aren't worth
+            // a look
+            if mv.is_capture() && !mv.is_promotion() && see < 0 {
+                continue;
             }
-        }); // Consider captures and checks
-
-        for mv in valid_moves.iter_mut() {
-            let mut new_board = self.clone();
-            new_board.make_move(mv.from, mv.to);
-            let score = -new_board.quiescence_search(-beta, -alpha);
+            // Delta pruning: skip captures that can't lift the score to
+            // alpha even with some positional slack
+            let gain = mv
+                .captured
+                .map_or(0, |piece| self.eval_params.piece_value(piece));
+            if !mv.is_promotion() && stand_pat + gain + DELTA_MARGIN <= alpha {
+                continue;
+            }
+            self.make_move(mv);
+            let score = -self.quiescence_search(-beta, -alpha, qply + 1);
+            self.undo_move();
             if score >= beta {
                 return beta;
             }
@@ -300,84 +1248,515 @@ impl Board {
         alpha
     }
 
-    fn minimax(&mut self, depth: usize, is_maximizing: bool, alpha: i32, beta: i32) -> i32 {
+    /// The moves quiescence search explores for the side to move: captures
+    /// and moves that give check.
+    pub fn quiescence_moves(&self) -> Vec<Move> {
+        let mut valid_moves = self.get_legal_moves(self.current_turn());
+        valid_moves.retain(|mv| mv.is_capture() || self.gives_check(mv));
+        valid_moves
+    }
+
+    /// Negamax search with alpha-beta pruning. Scores are from the
+    /// perspective of the side to move. The best line found is left in `pv`.
+    fn minimax(&mut self, depth: usize, alpha: i32, beta: i32, pv: &mut Vec<Move>) -> i32 {
+        pv.clear();
+        if self.is_out_of_time() {
+            return 0;
+        }
+        if depth > 0 {
+            // Leaves are counted by the quiescence search
+            self.nodes += 1;
+        }
+
         let board_hash = self.hash();
-        if let Some(&cached_eval) = self.transposition_table.get(&board_hash) {
-            return cached_eval;
+        let ply = self.history.len() + self.null_plies - self.root_ply;
+        // A position already seen in the game or on the way here can be
+        // repeated forever, so score it as the draw it leads to
+        if ply > 0 && self.position_counts.contains_key(&board_hash) {
+            return self.draw_score(ply);
+        }
+        // The fifty-move rule draws too, unless the move that reached it
+        // gave mate. Checked before the table, whose entries don't know the
+        // clock, and before the leaves, which never look for mate.
+        if ply > 0 && self.is_fifty_move_draw() {
+            return if self.is_checkmate(self.turn) {
+                -(MATE - ply as i32)
+            } else {
+                self.draw_score(ply)
+            };
+        }
+        let cached = self.transposition_table.get(board_hash);
+        if let Some(score) = cached.and_then(|entry| entry.usable_score(depth, alpha, beta, ply)) {
+            return score;
         }
 
+        let original_alpha = alpha;
         if depth == 0 {
-            let eval = self.quiescence_search(alpha, beta);
-            self.transposition_table.insert(board_hash, eval);
+            let eval = self.quiescence_search(alpha, beta, 0);
+            // Scores from an interrupted search are meaningless
+            if self.is_out_of_time() {
+                return 0;
+            }
+            self.transposition_table
+                .insert(board_hash, TtEntry::new(0, eval, alpha, beta, None, ply));
             return eval;
         }
 
-        let color = if is_maximizing {
-            Color::Black
-        } else {
-            Color::White
-        };
-        let mut valid_moves = self.get_valid_moves(color);
+        // If passing still fails high, a real move surely would. Not in
+        // check, where passing is illegal, nor anywhere below another pass,
+        // nor with only pawns left, where zugzwang makes passing a real
+        // advantage.
+        if self.null_move
+            && depth > NULL_MOVE_REDUCTION
+            && self.null_plies == 0
+            && !self.is_in_check(self.turn)
+            && self
+                .pieces(self.turn)
+                .any(|(_, piece)| !matches!(piece, Piece::King | Piece::Pawn))
+        {
+            let en_passant = self.make_null_move();
+            let score = -self.minimax(
+                depth - 1 - NULL_MOVE_REDUCTION,
+                -beta,
+                -beta + 1,
+                &mut Vec::new(),
+            );
+            self.undo_null_move(en_passant);
+            if score >= beta && !self.is_out_of_time() {
+                return beta;
+            }
+        }
 
-        let mut alpha = alpha;
-        let mut beta = beta;
-        let mut best_eval = if is_maximizing { i32::MIN } else { i32::MAX };
+        let mut valid_moves = self.get_legal_moves(self.turn);
+        if valid_moves.is_empty() {
+            // Mated, or stalemated, which is a draw
+            return if self.is_in_check(self.turn) {
+                -(MATE - ply as i32)
+            } else {
+                self.draw_score(ply)
+            };
+        }
+        if self.move_heuristics {
+            self.order_quiet_moves(&mut valid_moves, ply);
+        }
+        // The best move from an earlier search of this position goes first
+        if let Some(index) = cached
+            .and_then(|entry| entry.best_move)
+            .and_then(|best| valid_moves.iter().position(|mv| *mv == best))
+        {
+            valid_moves[..=index].rotate_right(1);
+        }
 
-        for mv in valid_moves.iter_mut() {
-            let mut new_board = self.clone();
-            new_board.make_move(mv.from, mv.to);
-            let eval = new_board.minimax(depth - 1, !is_maximizing, alpha, beta);
-            if is_maximizing {
-                best_eval = best_eval.max(eval);
-                alpha = alpha.max(eval);
+        let mut alpha = alpha;
+        let mut best_eval = -INFINITY;
+        let mut best_move = None;
+
+        let in_check = self.is_in_check(self.turn);
+        let mut child_pv = Vec::new();
+        for (index, mv) in valid_moves.iter().enumerate() {
+            self.make_move(*mv);
+            // Quiet moves this late rarely raise alpha, so first check that
+            // with a shallower null-window search
+            let reduce = self.late_move_reductions
+                && index >= LMR_FULL_DEPTH_MOVES
+                && depth >= LMR_MIN_DEPTH
+                && !in_check
+                && mv.is_quiet()
+                && !self.is_in_check(self.turn);
+            let mut eval = if reduce {
+                -self.minimax(depth - 2, -alpha - 1, -alpha, &mut child_pv)
             } else {
-                best_eval = best_eval.min(eval);
-                beta = beta.min(eval);
+                INFINITY
+            };
+            if eval > alpha {
+                eval = -self.minimax(depth - 1, -beta, -alpha, &mut child_pv);
+            }
+            self.undo_move();
+            if eval > best_eval {
+                best_eval = eval;
+                best_move = Some(*mv);
+            }
+            if eval > alpha {
+                alpha = eval;
+                pv.clear();
+                pv.push(*mv);
+                pv.append(&mut child_pv);
             }
-            if beta <= alpha {
+            if alpha >= beta {
+                if self.move_heuristics && !mv.is_capture() {
+                    self.record_cutoff(*mv, depth, ply);
+                }
                 break;
             }
         }
 
-        self.transposition_table.insert(board_hash, best_eval);
+        // Scores from an interrupted search are meaningless
+        if self.is_out_of_time() {
+            return 0;
+        }
+        self.transposition_table.insert(
+            board_hash,
+            TtEntry::new(depth, best_eval, original_alpha, beta, best_move, ply),
+        );
         best_eval
     }
 
-    pub fn find_best_move(&mut self) -> Option<Move> {
-        let color = self.turn;
-        let mut best_move = None;
-        let mut best_score = if color == Color::Black {
-            i32::MIN
+    /// Passes the turn, returning the en passant square to restore.
+    fn make_null_move(&mut self) -> Option<Position> {
+        let keys = zobrist::keys();
+        self.null_plies += 1;
+        self.zobrist ^= keys.black_to_move;
+        if let Some(en_passant) = self.en_passant {
+            self.zobrist ^= keys.en_passant_file[en_passant.col];
+        }
+        self.turn = match self.turn {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        };
+        self.en_passant.take()
+    }
+
+    fn undo_null_move(&mut self, en_passant: Option<Position>) {
+        let keys = zobrist::keys();
+        self.null_plies -= 1;
+        self.zobrist ^= keys.black_to_move;
+        if let Some(en_passant) = en_passant {
+            self.zobrist ^= keys.en_passant_file[en_passant.col];
+        }
+        self.turn = match self.turn {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        };
+        self.en_passant = en_passant;
+    }
+
+    /// Sorts the quiet moves, which follow the captures, so that killer moves
+    /// come first and the rest go by history score.
+    fn order_quiet_moves(&self, moves: &mut [Move], ply: usize) {
+        let first_quiet = moves
+            .iter()
+            .position(|mv| !mv.is_capture())
+            .unwrap_or(moves.len());
+        let killers = self.killers.get(ply).copied().unwrap_or([None; 2]);
+        moves[first_quiet..].sort_by_key(|mv| {
+            Reverse(if killers[0] == Some(*mv) {
+                i32::MAX
+            } else if killers[1] == Some(*mv) {
+                i32::MAX - 1
+            } else {
+                self.history_scores
+                    .get(&(mv.piece, mv.to))
+                    .copied()
+                    .unwrap_or(0)
+            })
+        });
+    }
+
+    /// Remembers a quiet move that caused a beta cutoff at `ply`.
+    fn record_cutoff(&mut self, mv: Move, depth: usize, ply: usize) {
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; 2]);
+        }
+        let slots = &mut self.killers[ply];
+        if slots[0] != Some(mv) {
+            slots[1] = slots[0];
+            slots[0] = Some(mv);
+        }
+        *self.history_scores.entry((mv.piece, mv.to)).or_insert(0) += (depth * depth) as i32;
+    }
+
+    /// Limits the transposition table to `entries` positions, dropping
+    /// whatever it held. Newer results evict older ones once it is full.
+    pub fn set_tt_size(&mut self, entries: usize) {
+        self.transposition_table = TranspositionTable::new(entries);
+    }
+
+    /// Forgets all cached search results.
+    pub fn clear_tt(&mut self) {
+        self.transposition_table.clear();
+    }
+
+    /// Hands the move to `color`. The en passant square goes, as it only
+    /// ever stands for the one reply to a double pawn push. Does nothing if
+    /// `color` is already to move.
+    pub fn set_turn(&mut self, color: Color) {
+        if self.turn == color {
+            return;
+        }
+        self.turn = color;
+        self.en_passant = None;
+        self.refresh_hash();
+    }
+
+    /// Replaces the weights the evaluation uses.
+    pub fn set_eval_params(&mut self, params: EvalParams) {
+        self.eval_params = params;
+    }
+
+    /// Sets how many tenths of a pawn the engine would rather be behind than
+    /// draw by repetition, stalemate or the fifty-move rule. Negative
+    /// values make it seek draws instead.
+    pub fn set_contempt(&mut self, contempt: i32) {
+        self.contempt = contempt;
+    }
+
+    /// The score of a draw `ply` plies into the search, for the side to move
+    /// there. The root side's contempt counts against it, and for the other.
+    fn draw_score(&self, ply: usize) -> i32 {
+        if ply.is_multiple_of(2) {
+            -self.contempt
         } else {
-            i32::MAX
+            self.contempt
+        }
+    }
+
+    /// Seeds the tie-breaker between root moves that score the same and
+    /// look equally good otherwise, so games can be replayed exactly.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    /// Lets `find_best_move` play from `book` in the positions it covers.
+    pub fn set_book(&mut self, book: Book) {
+        self.book = Some(Arc::new(book));
+    }
+
+    /// Positions visited by the latest search, quiescence included.
+    pub fn nodes_searched(&self) -> u64 {
+        self.nodes
+    }
+
+    pub fn find_best_move(&mut self) -> Option<Move> {
+        if let Some(mv) = self.book.as_deref().and_then(|book| self.book_move(book)) {
+            return Some(mv);
+        }
+        self.search(MAX_DEPTH).best_move
+    }
+
+    /// Searches to `depth` and returns the best move along with its score
+    /// and principal variation.
+    pub fn search(&mut self, depth: usize) -> SearchResult {
+        self.search_with_callback(depth, |_| {})
+    }
+
+    /// The score of playing `mv` here, found the way `search` scores a root
+    /// move: the reply gets `depth` plies, but with a full window so the
+    /// score is exact. Expects the search state to be set up already.
+    pub(crate) fn score_move(&mut self, mv: Move, depth: usize) -> i32 {
+        self.make_move(mv);
+        let score = -self.minimax(depth, -INFINITY, INFINITY, &mut Vec::new());
+        self.undo_move();
+        score
+    }
+
+    /// Up to `n` root moves with their scores from searching `depth`, best
+    /// first, for showing more than one good move. The first is the move
+    /// `search` picks, with its score. The rest are searched again with a
+    /// full window so they are scored exactly, which makes this slower than
+    /// `search`. None of them is ranked above the first, even where that
+    /// search scores it higher. Like `search`, a depth of 0 finds no moves.
+    pub fn top_moves(&mut self, depth: usize, n: usize) -> Vec<(Move, i32)> {
+        let best = self.search(depth);
+        let Some(best_move) = best.best_move else {
+            return Vec::new();
         };
+        let mut scored = vec![(best_move, best.score)];
+        for mv in self.get_legal_moves(self.turn) {
+            if mv != best_move {
+                let score = self.score_move(mv, depth).min(best.score);
+                scored.push((mv, score));
+            }
+        }
+        // Stable, so ties stay behind the searched move
+        scored.sort_by_key(|&(_, score)| Reverse(score));
+        scored.truncate(n);
+        scored
+    }
 
-        for depth in 1..=MAX_DEPTH {
-            let mut valid_moves = self.get_valid_moves(color);
-            for mv in valid_moves.iter_mut() {
-                let mut new_board = self.clone();
-                new_board.make_move(mv.from, mv.to);
-                let score = new_board.minimax(depth, color == Color::White, i32::MIN, i32::MAX);
-                if (color == Color::Black && score > best_score)
-                    || (color == Color::White && score < best_score)
-                {
-                    best_score = score;
-                    best_move = Some(*mv);
-                } else if score == best_score {
-                    // Apply secondary criteria
-                    if self.more_criteria(&mv, &best_move.unwrap()) {
-                        best_move = Some(*mv);
+    /// Like `search`, but reports each completed depth to `on_info` as it
+    /// finishes.
+    pub fn search_with_callback(
+        &mut self,
+        max_depth: usize,
+        mut on_info: impl FnMut(SearchInfo),
+    ) -> SearchResult {
+        self.deadline = None;
+        self.iterative_deepening(max_depth, &mut on_info)
+    }
+
+    /// Like `find_best_move`, but with `threads` searching at once in the
+    /// "lazy SMP" style: helper threads search copies of the board, some a
+    /// ply deeper, and share this board's transposition table, so the main
+    /// search picks up their results as cutoffs and move ordering. The move
+    /// returned is the main search's; which one that is may vary from run
+    /// to run when several are as good.
+    pub fn find_best_move_parallel(&mut self, threads: usize) -> Option<Move> {
+        if let Some(mv) = self.book.as_deref().and_then(|book| self.book_move(book)) {
+            return Some(mv);
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let helpers: Vec<Board> = (1..threads)
+            .map(|index| {
+                let mut helper = self.clone();
+                helper.transposition_table = self.transposition_table.share();
+                helper.stop = Some(Arc::clone(&stop));
+                // Different tie-breaks and depths keep the helpers from all
+                // walking the same tree in the same order
+                helper.seed = Some(index as u64);
+                helper
+            })
+            .collect();
+
+        thread::scope(|scope| {
+            for (index, mut helper) in helpers.into_iter().enumerate() {
+                scope.spawn(move || helper.search(MAX_DEPTH + index % 2));
+            }
+            let result = self.search(MAX_DEPTH);
+            stop.store(true, Ordering::Relaxed);
+            result.best_move
+        })
+    }
+
+    /// Deepens the search until `budget` runs out and returns the best move of
+    /// the last fully completed depth. Always returns a legal move when one
+    /// exists, even if not a single depth could be completed in time.
+    pub fn find_best_move_timed(&mut self, budget: Duration) -> Option<Move> {
+        self.deadline = Some(Instant::now() + budget);
+        let result = self.iterative_deepening(MAX_TIMED_DEPTH, &mut |_| {});
+        self.deadline = None;
+        result
+            .best_move
+            .or_else(|| self.get_legal_moves(self.turn).first().copied())
+    }
+
+    fn iterative_deepening(
+        &mut self,
+        max_depth: usize,
+        on_info: &mut dyn FnMut(SearchInfo),
+    ) -> SearchResult {
+        let mut result = SearchResult {
+            best_move: None,
+            score: 0,
+            pv: Vec::new(),
+        };
+        self.completed_depth = 0;
+        self.nodes = 0;
+        self.killers.clear();
+        self.history_scores.clear();
+        self.root_ply = self.history.len();
+        // The position fields are public, so they may have been edited
+        // since the hash was last brought up to date
+        self.refresh_hash();
+
+        'deepening: for depth in 1..=max_depth {
+            let mut delta = ASPIRATION_WINDOW;
+            let (mut alpha, mut beta) = if self.aspiration_windows && depth > 1 {
+                (result.score - delta, result.score + delta)
+            } else {
+                (-INFINITY, INFINITY)
+            };
+            loop {
+                match self.search_root(depth, result.best_move, alpha, beta) {
+                    // The score fell outside the window, so it is only a
+                    // bound; widen that side and search again
+                    Some(bound) if bound.score <= alpha => {
+                        delta *= 4;
+                        alpha = (bound.score - delta).max(-INFINITY);
                     }
+                    Some(bound) if bound.score >= beta => {
+                        delta *= 4;
+                        beta = (bound.score + delta).min(INFINITY);
+                    }
+                    Some(completed) => {
+                        result = completed;
+                        self.completed_depth = depth;
+                        on_info(SearchInfo {
+                            depth,
+                            nodes: self.nodes,
+                            score: result.score,
+                            pv: result.pv.clone(),
+                        });
+                        break;
+                    }
+                    None => break 'deepening, // Out of time, or no legal moves at all
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Searches every root move to `depth` within the `alpha`..`beta`
+    /// window, trying `previous_best` first. A score outside the window is
+    /// only a bound. Returns `None` if the deadline passed before the
+    /// iteration finished.
+    fn search_root(
+        &mut self,
+        depth: usize,
+        previous_best: Option<Move>,
+        alpha: i32,
+        beta: i32,
+    ) -> Option<SearchResult> {
+        let mut best: Option<SearchResult> = None;
+        let mut valid_moves = self.get_legal_moves(self.turn);
+        if let Some(index) =
+            previous_best.and_then(|best| valid_moves.iter().position(|mv| *mv == best))
+        {
+            valid_moves[..=index].rotate_right(1);
+        }
+
+        let mut child_pv = Vec::new();
+        for mv in valid_moves.iter_mut() {
+            // Keep alpha one below the best score so ties are exact and
+            // can go to the secondary criteria
+            let floor = best
+                .as_ref()
+                .map_or(alpha, |best| alpha.max(best.score - 1));
+            self.make_move(*mv);
+            let score = -self.minimax(depth, -beta, -floor, &mut child_pv);
+            self.undo_move();
+            if self.is_out_of_time() {
+                return None;
+            }
+
+            let improves = match &best {
+                None => true,
+                Some(best) if score > best.score => true,
+                // Apply secondary criteria
+                Some(best) if score == best.score => {
+                    self.more_criteria(mv, &best.best_move.unwrap())
                 }
+                Some(_) => false,
+            };
+            if improves {
+                let mut pv = vec![*mv];
+                pv.append(&mut child_pv);
+                best = Some(SearchResult {
+                    best_move: Some(*mv),
+                    score,
+                    pv,
+                });
+            }
+            if score >= beta {
+                break;
             }
         }
 
-        best_move
+        best
+    }
+
+    fn is_out_of_time(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+            || self
+                .stop
+                .as_ref()
+                .is_some_and(|stop| stop.load(Ordering::Relaxed))
     }
 
     fn more_criteria(&self, mv1: &Move, mv2: &Move) -> bool {
         // Example secondary criteria: prefer moves that control the center
-        let center_squares = vec![
+        let center_squares = [
             Position { row: 3, col: 3 },
             Position { row: 3, col: 4 },
             Position { row: 4, col: 3 },
@@ -403,167 +1782,955 @@ impl Board {
             return false;
         }
 
-        // Additional secondary criteria can be added here
-
-        false
+        match self.seed {
+            Some(seed) => tie_break_key(seed, mv1) > tie_break_key(seed, mv2),
+            None => false,
+        }
     }
 
     fn get_valid_moves_after_move(&self, mv: &Move) -> Vec<Move> {
         let mut new_board = self.clone();
-        new_board.make_move(mv.from, mv.to);
+        new_board.make_move(*mv);
         new_board.get_valid_moves(new_board.turn)
     }
 
-    pub fn make_move(&mut self, from: Position, to: Position) {
-        let piece = self.squares[from.row][from.col].piece;
-        let color = self.squares[from.row][from.col].color;
-        self.squares[to.row][to.col] = Square::new(piece, color);
+    /// Validates `mv` against the legal moves for the side to move before
+    /// applying it. Only the from/to squares and promotion are compared.
+    pub fn try_make_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        if let Some(pos) = [mv.from, mv.to].into_iter().find(|pos| !pos.is_valid()) {
+            return Err(MoveError::OffBoard(pos));
+        }
+        let square = self.squares[mv.from.row][mv.from.col];
+        if square.piece == Piece::Empty {
+            return Err(MoveError::EmptySquare(mv.from));
+        }
+        if square.color != Some(self.turn) {
+            return Err(MoveError::WrongColor(mv.from));
+        }
+
+        let legal_move = self
+            .get_legal_moves(self.turn)
+            .into_iter()
+            .find(|legal| {
+                legal.from == mv.from && legal.to == mv.to && legal.promotion == mv.promotion
+            })
+            .ok_or(MoveError::IllegalMove(mv))?;
+        self.make_move(legal_move);
+        Ok(())
+    }
+
+    /// Applies `mv` without any validation; used by the search.
+    pub fn make_move(&mut self, mv: Move) {
+        let (from, to) = (mv.from, mv.to);
+        debug_assert!(from.is_valid() && to.is_valid(), "move off the board");
+        *self.position_counts.entry(self.hash()).or_insert(0) += 1;
+        let moved = self.squares[from.row][from.col];
+        let piece = mv.promotion.unwrap_or(moved.piece);
+        let color = moved.color;
+        let moving = moved.piece;
+        let castled = self.castling_side(&mv);
+        // Where the moving piece lands; castling may not move the king
+        // to `to`
+        let landing = castled.map_or(to, |side| Position {
+            row: from.row,
+            col: side.destination_cols().0,
+        });
+
+        // En passant removes the pawn that passed the target square
+        let captured_pos =
+            if moving == Piece::Pawn && from.col != to.col && Some(to) == self.en_passant {
+                Position {
+                    row: from.row,
+                    col: to.col,
+                }
+            } else {
+                to
+            };
+        // A castling king "taking" its own rook captures nothing
+        let captured = match castled {
+            Some(_) => Square::new(Piece::Empty, None),
+            None => self.squares[captured_pos.row][captured_pos.col],
+        };
+        self.history.push(HistoryEntry {
+            mv,
+            castled,
+            moved,
+            captured,
+            captured_pos,
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            zobrist: self.zobrist,
+        });
+        let keys = zobrist::keys();
+        self.zobrist ^= keys.square(moved, from.row, from.col)
+            ^ keys.square(captured, captured_pos.row, captured_pos.col)
+            ^ keys.square(Square::new(piece, color), landing.row, landing.col)
+            ^ keys.castling_rights(self.castling)
+            ^ keys.black_to_move;
+        if let Some(en_passant) = self.en_passant {
+            self.zobrist ^= keys.en_passant_file[en_passant.col];
+        }
+        if moving == Piece::Pawn || captured.piece != Piece::Empty {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if castled.is_none() {
+            self.squares[captured_pos.row][captured_pos.col] = Square::new(Piece::Empty, None);
+        }
+
+        self.en_passant = if moving == Piece::Pawn && from.row.abs_diff(to.row) == 2 {
+            Some(Position {
+                row: (from.row + to.row) / 2,
+                col: from.col,
+            })
+        } else {
+            None
+        };
+        if let Some(en_passant) = self.en_passant {
+            self.zobrist ^= keys.en_passant_file[en_passant.col];
+        }
+
+        self.update_castling_rights(from, to, moving, color);
+        self.zobrist ^= keys.castling_rights(self.castling);
+
+        // Lift both pieces before placing either, as in Chess960 each may
+        // land where the other stood
         self.squares[from.row][from.col] = Square::new(Piece::Empty, None);
+        // Castling also moves the rook to the other side of the king
+        if let Some(side) = castled {
+            let rook_from = self.castling_rook_cols[side as usize];
+            let rook_to = side.destination_cols().1;
+            let rook = self.squares[from.row][rook_from];
+            self.zobrist ^=
+                keys.square(rook, from.row, rook_from) ^ keys.square(rook, from.row, rook_to);
+            self.squares[from.row][rook_from] = Square::new(Piece::Empty, None);
+            self.squares[from.row][rook_to] = rook;
+        }
+        self.squares[landing.row][landing.col] = Square::new(piece, color);
+        if let (Piece::King, Some(color)) = (moving, color) {
+            self.kings[color_index(color)] = Some(landing);
+        }
+        if let (Piece::King, Some(color)) = (captured.piece, captured.color) {
+            // Only possible in pseudo-legal lines
+            self.kings[color_index(color)] = None;
+        }
+        if self.turn == Color::Black {
+            self.fullmove_number += 1;
+        }
         self.turn = match self.turn {
             Color::White => Color::Black,
             Color::Black => Color::White,
         };
     }
 
-    pub fn is_checkmate(&self, color: Color) -> bool {
-        // Check if the current player is in checkmate
-        let valid_moves = self.get_valid_moves(color);
-        valid_moves.is_empty() && self.is_in_check(color)
-    }
-
-    fn is_in_check(&self, color: Color) -> bool {
-        // Find the king's position
-        let king_pos = self.find_king(color);
-        if let Some((king_i, king_j)) = king_pos {
-            // Check if any opponent piece can move to the king's position
-            let opponent_color = if color == Color::White {
-                Color::Black
-            } else {
-                Color::White
-            };
-            let opponent_moves = self.get_valid_moves(opponent_color);
-            for mv in opponent_moves {
-                if mv.to
-                    == (Position {
-                        row: king_i,
-                        col: king_j,
-                    })
-                {
-                    return true;
+    /// Takes back the last move made with `make_move`, returning it, or
+    /// `None` if there is no history.
+    pub fn undo_move(&mut self) -> Option<Move> {
+        let entry = self.history.pop()?;
+        let (from, to) = (entry.mv.from, entry.mv.to);
+
+        if let Some(side) = entry.castled {
+            // Put the king and rook back where they castled from
+            let (king_to, rook_to) = side.destination_cols();
+            let rook = self.squares[from.row][rook_to];
+            self.squares[from.row][king_to] = Square::new(Piece::Empty, None);
+            self.squares[from.row][rook_to] = Square::new(Piece::Empty, None);
+            self.squares[from.row][self.castling_rook_cols[side as usize]] = rook;
+            self.squares[from.row][from.col] = entry.moved;
+        } else {
+            self.squares[to.row][to.col] = Square::new(Piece::Empty, None);
+            self.squares[from.row][from.col] = entry.moved;
+            self.squares[entry.captured_pos.row][entry.captured_pos.col] = entry.captured;
+        }
+
+        for (square, pos) in [(entry.moved, from), (entry.captured, entry.captured_pos)] {
+            if let (Piece::King, Some(color)) = (square.piece, square.color) {
+                self.kings[color_index(color)] = Some(pos);
+            }
+        }
+
+        self.castling = entry.castling;
+        self.en_passant = entry.en_passant;
+        self.halfmove_clock = entry.halfmove_clock;
+        self.zobrist = entry.zobrist;
+        self.turn = match self.turn {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        };
+        if self.turn == Color::Black {
+            self.fullmove_number -= 1;
+        }
+
+        let hash = self.hash();
+        if let Some(count) = self.position_counts.get_mut(&hash) {
+            *count -= 1;
+            if *count == 0 {
+                self.position_counts.remove(&hash);
+            }
+        }
+        Some(entry.mv)
+    }
+
+    /// Counts the leaf nodes of the legal move tree to `depth`, for checking
+    /// move generation against known reference values.
+    pub fn perft(&mut self, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let legal_moves = self.get_legal_moves(self.turn);
+        if depth == 1 {
+            return legal_moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+        for mv in legal_moves {
+            self.make_move(mv);
+            nodes += self.perft(depth - 1);
+            self.undo_move();
+        }
+        nodes
+    }
+
+    /// Splits `perft(depth)` by root move, sorted by SAN, to help pinpoint
+    /// which move a move generation bug hides behind.
+    pub fn perft_divide(&mut self, depth: usize) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        let mut split: Vec<(String, Move, u64)> = self
+            .get_legal_moves(self.turn)
+            .into_iter()
+            .map(|mv| {
+                let san = self.move_to_san(&mv);
+                self.make_move(mv);
+                let nodes = self.perft(depth - 1);
+                self.undo_move();
+                (san, mv, nodes)
+            })
+            .collect();
+        split.sort_by(|a, b| a.0.cmp(&b.0));
+        split
+            .into_iter()
+            .map(|(_, mv, nodes)| (mv, nodes))
+            .collect()
+    }
+
+    /// Drops castling rights when a king moves, or when a rook moves from or
+    /// is captured on its original square.
+    fn update_castling_rights(
+        &mut self,
+        from: Position,
+        to: Position,
+        moving: Piece,
+        color: Option<Color>,
+    ) {
+        if moving == Piece::King {
+            match color {
+                Some(Color::White) => {
+                    self.castling.white_kingside = false;
+                    self.castling.white_queenside = false;
+                }
+                Some(Color::Black) => {
+                    self.castling.black_kingside = false;
+                    self.castling.black_queenside = false;
                 }
+                None => {}
+            }
+        }
+        let [queenside_col, kingside_col] = self.castling_rook_cols;
+        for pos in [from, to] {
+            match (pos.row, pos.col) {
+                (0, col) if col == queenside_col => self.castling.white_queenside = false,
+                (0, col) if col == kingside_col => self.castling.white_kingside = false,
+                (7, col) if col == queenside_col => self.castling.black_queenside = false,
+                (7, col) if col == kingside_col => self.castling.black_kingside = false,
+                _ => {}
             }
         }
-        false
     }
 
-    fn find_king(&self, color: Color) -> Option<(usize, usize)> {
+    /// Whether 50 full moves have passed without a capture or pawn move.
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove_clock >= 100
+    }
+
+    /// Whether the current position has occurred at least three times, with
+    /// the same side to move, castling rights and en passant square.
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.position_counts.get(&self.hash()).copied().unwrap_or(0) >= 2
+    }
+
+    /// Whether neither side can possibly mate: K vs K, K+minor vs K, or
+    /// K+B vs K+B with both bishops on squares of the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut minors = Vec::new();
         for (i, row) in self.squares.iter().enumerate() {
             for (j, square) in row.iter().enumerate() {
-                if square.piece == Piece::King && square.color == Some(color) {
-                    return Some((i, j));
+                match square.piece {
+                    Piece::King | Piece::Empty => {}
+                    Piece::Bishop | Piece::Knight => {
+                        minors.push((square.piece, square.color, i, j))
+                    }
+                    Piece::Pawn | Piece::Rook | Piece::Queen => return false,
                 }
             }
         }
-        None
-    }
 
-    fn hash(&self) -> u64 {
-        // Implement a hashing function for the board state
-        0 // Placeholder
+        match minors.as_slice() {
+            [] | [_] => true,
+            [(Piece::Bishop, first_color, i1, j1), (Piece::Bishop, second_color, i2, j2)] => {
+                first_color != second_color && (i1 + j1) % 2 == (i2 + j2) % 2
+            }
+            _ => false,
+        }
     }
 
-    fn current_turn(&self) -> Color {
-        // Implement logic to determine the current turn
-        Color::White // Placeholder
+    pub fn is_stalemate(&self, color: Color) -> bool {
+        !self.is_in_check(color) && !self.has_legal_move(color)
     }
-}
 
-impl Clone for Board {
-    fn clone(&self) -> Self {
-        let mut new_squares = [[Square::new(Piece::Empty, None); 8]; 8];
-        for i in 0..8 {
-            for j in 0..8 {
-                new_squares[i][j] = self.squares[i][j];
+    /// The state of the game from the perspective of the side to move.
+    pub fn result(&self) -> GameResult {
+        if self.is_checkmate(self.turn) {
+            let winner = match self.turn {
+                Color::White => Color::Black,
+                Color::Black => Color::White,
+            };
+            GameResult::Checkmate { winner }
+        } else if self.is_stalemate(self.turn) {
+            GameResult::Stalemate
+        } else if self.is_fifty_move_draw() {
+            GameResult::Draw {
+                reason: DrawReason::FiftyMoveRule,
+            }
+        } else if self.is_threefold_repetition() {
+            GameResult::Draw {
+                reason: DrawReason::ThreefoldRepetition,
             }
+        } else if self.is_insufficient_material() {
+            GameResult::Draw {
+                reason: DrawReason::InsufficientMaterial,
+            }
+        } else {
+            GameResult::Ongoing
         }
-        Board {
-            squares: new_squares,
-            transposition_table: self.transposition_table.clone(),
-            turn: self.turn,
+    }
+
+    /// How the game ended, or `None` while it is still going.
+    pub fn is_game_over(&self) -> Option<GameResult> {
+        match self.result() {
+            GameResult::Ongoing => None,
+            result => Some(result),
         }
     }
-}
 
-impl fmt::Display for Board {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for row in self.squares.iter() {
-            for square in row.iter() {
-                let symbol = match (square.piece, square.color) {
-                    (_, None) | (Piece::Empty, _) => ".",
-                    (Piece::King, Some(Color::White)) => "♔",
-                    (Piece::Queen, Some(Color::White)) => "♕",
-                    (Piece::Rook, Some(Color::White)) => "♖",
-                    (Piece::Bishop, Some(Color::White)) => "♗",
-                    (Piece::Knight, Some(Color::White)) => "♘",
-                    (Piece::Pawn, Some(Color::White)) => "♙",
-                    (Piece::King, Some(Color::Black)) => "♚",
-                    (Piece::Queen, Some(Color::Black)) => "♛",
-                    (Piece::Rook, Some(Color::Black)) => "♜",
-                    (Piece::Bishop, Some(Color::Black)) => "♝",
-                    (Piece::Knight, Some(Color::Black)) => "♞",
-                    (Piece::Pawn, Some(Color::Black)) => "♟︎",
-                };
-                write!(f, "{} ", symbol)?;
-            }
-            writeln!(f)?;
+    pub fn is_checkmate(&self, color: Color) -> bool {
+        self.is_in_check(color) && !self.has_legal_move(color)
+    }
+
+    /// Whether playing `mv` puts the opponent in check.
+    /// Works on a copy of the squares rather than playing the move, as the
+    /// quiescence search asks this about every quiet move.
+    pub fn gives_check(&self, mv: &Move) -> bool {
+        let Some(mover) = self.squares[mv.from.row][mv.from.col].color else {
+            return false;
+        };
+        let opponent = match mover {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        };
+        let empty = Square::new(Piece::Empty, None);
+
+        let mut squares = self.squares;
+        let mut landing = mv.to;
+        squares[mv.from.row][mv.from.col] = empty;
+        if let Some(side) = self.castling_side(mv) {
+            let (king_col, rook_col) = side.destination_cols();
+            squares[mv.from.row][self.castling_rook_cols[side as usize]] = empty;
+            squares[mv.from.row][rook_col] = Square::new(Piece::Rook, Some(mover));
+            landing.col = king_col;
+        } else if mv.piece == Piece::Pawn
+            && mv.from.col != mv.to.col
+            && Some(mv.to) == self.en_passant
+        {
+            squares[mv.from.row][mv.to.col] = empty;
         }
-        Ok(())
+        squares[landing.row][landing.col] =
+            Square::new(mv.promotion.unwrap_or(mv.piece), Some(mover));
+
+        self.find_king(opponent).is_some_and(|(row, col)| {
+            Self::visit_attackers(&squares, Position { row, col }, mover, |_| true)
+        })
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::{Board, Color, Move, Piece, Position};
+    /// Whether playing `mv` checkmates the opponent. The move is made and
+    /// taken back, leaving the board as it was.
+    pub fn gives_checkmate(&mut self, mv: &Move) -> bool {
+        self.make_move(*mv);
+        let checkmate = self.is_checkmate(self.turn);
+        self.undo_move();
+        checkmate
+    }
 
-    #[test]
-    fn test_get_valid_moves_white_pawn_initial_position() {
-        let board = Board::new();
-        let moves = board.get_valid_moves(Color::White);
+    pub fn is_in_check(&self, color: Color) -> bool {
+        let opponent = match color {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        };
+        self.find_king(color)
+            .is_some_and(|(row, col)| self.is_square_attacked(Position { row, col }, opponent))
+    }
 
-        let expected_moves = vec![
-            Move {
-                from: Position { row: 1, col: 0 },
-                to: Position { row: 2, col: 0 },
-                piece: Piece::Pawn,
-                captured: None,
-                score: 0,
-            },
-            Move {
-                from: Position { row: 1, col: 1 },
-                to: Position { row: 2, col: 1 },
-                piece: Piece::Pawn,
-                captured: None,
-                score: 0,
-            },
-            Move {
-                from: Position { row: 1, col: 2 },
-                to: Position { row: 2, col: 2 },
-                piece: Piece::Pawn,
-                captured: None,
-                score: 0,
-            },
-            Move {
-                from: Position { row: 1, col: 3 },
-                to: Position { row: 2, col: 3 },
-                piece: Piece::Pawn,
-                captured: None,
-                score: 0,
-            },
-            Move {
-                from: Position { row: 1, col: 4 },
-                to: Position { row: 2, col: 4 },
+    /// Whether any piece of color `by` attacks `pos`.
+    pub fn is_square_attacked(&self, pos: Position, by: Color) -> bool {
+        Self::visit_attackers(&self.squares, pos, by, |_| true)
+    }
+
+    /// Where the pieces of color `by` that attack `pos` stand.
+    pub fn attackers_of(&self, pos: Position, by: Color) -> Vec<Position> {
+        let mut attackers = Vec::new();
+        Self::visit_attackers(&self.squares, pos, by, |attacker| {
+            attackers.push(attacker);
+            false
+        });
+        attackers
+    }
+
+    /// Static exchange evaluation: the material the side making `mv` comes
+    /// out ahead (or behind, if negative) once both sides have recaptured on
+    /// the target square for as long as it pays. A quiet move scores below
+    /// 0 when it puts the piece where it can be won.
+    pub fn see(&self, mv: &Move) -> i32 {
+        let Some(mover) = self.squares[mv.from.row][mv.from.col].color else {
+            return 0;
+        };
+        // The king can't be won, so it only matters as a last recapturer
+        let value = |piece: Piece| match piece {
+            Piece::King => piece.value(),
+            piece => self.eval_params.piece_value(piece),
+        };
+        let target = mv.to;
+        let empty = Square::new(Piece::Empty, None);
+
+        let mut squares = self.squares;
+        squares[mv.from.row][mv.from.col] = empty;
+        if mv.piece == Piece::Pawn && mv.from.col != target.col && Some(target) == self.en_passant {
+            squares[mv.from.row][target.col] = empty;
+        }
+        let mut on_target = mv.promotion.unwrap_or(mv.piece);
+        squares[target.row][target.col] = Square::new(on_target, Some(mover));
+
+        // What the side capturing at each step has won so far, if the
+        // exchange stopped there
+        let mut gains = vec![mv.captured.map_or(0, value)];
+        let mut side = match mover {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        };
+        loop {
+            let mut attacker: Option<Position> = None;
+            Self::visit_attackers(&squares, target, side, |pos| {
+                let piece = squares[pos.row][pos.col].piece;
+                if attacker
+                    .is_none_or(|best| value(piece) < value(squares[best.row][best.col].piece))
+                {
+                    attacker = Some(pos);
+                }
+                false
+            });
+            let Some(attacker) = attacker else {
+                break;
+            };
+            gains.push(value(on_target) - gains[gains.len() - 1]);
+            on_target = squares[attacker.row][attacker.col].piece;
+            squares[attacker.row][attacker.col] = empty;
+            squares[target.row][target.col] = Square::new(on_target, Some(side));
+            side = match side {
+                Color::White => Color::Black,
+                Color::Black => Color::White,
+            };
+        }
+
+        // Either side may decline to recapture, so unwind from the end
+        gains
+            .into_iter()
+            .rev()
+            .reduce(|later, gain| -(-gain).max(later))
+            .unwrap_or(0)
+    }
+
+    /// Calls `visit` with each piece of color `by` attacking `pos` on
+    /// `squares`, looking outward from the square along each way a piece
+    /// could reach it. Stops and returns true as soon as `visit` does.
+    fn visit_attackers(
+        squares: &[[Square; 8]; 8],
+        pos: Position,
+        by: Color,
+        mut visit: impl FnMut(Position) -> bool,
+    ) -> bool {
+        let attacker_at = |di: isize, dj: isize, piece: Piece| {
+            let row = pos.row as isize + di;
+            let col = pos.col as isize + dj;
+            if on_board(row, col) {
+                let (row, col) = (row as usize, col as usize);
+                Some(Position { row, col })
+                    .filter(|_| squares[row][col] == Square::new(piece, Some(by)))
+            } else {
+                None
+            }
+        };
+
+        // Pawns capture towards the opponent, so look back the other way
+        let pawn_row = match by {
+            Color::White => -1,
+            Color::Black => 1,
+        };
+        for dj in [-1, 1] {
+            if let Some(attacker) = attacker_at(pawn_row, dj, Piece::Pawn) {
+                if visit(attacker) {
+                    return true;
+                }
+            }
+        }
+        for piece in [Piece::Knight, Piece::King] {
+            for (di, dj) in piece.directions() {
+                if let Some(attacker) = attacker_at(di, dj, piece) {
+                    if visit(attacker) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        for slider in [Piece::Rook, Piece::Bishop] {
+            for (di, dj) in slider.directions() {
+                let (mut row, mut col) = (pos.row as isize + di, pos.col as isize + dj);
+                while on_board(row, col) {
+                    let square = squares[row as usize][col as usize];
+                    if square.piece != Piece::Empty {
+                        if square.color == Some(by)
+                            && (square.piece == slider || square.piece == Piece::Queen)
+                        {
+                            let attacker = Position {
+                                row: row as usize,
+                                col: col as usize,
+                            };
+                            if visit(attacker) {
+                                return true;
+                            }
+                        }
+                        break;
+                    }
+                    row += di;
+                    col += dj;
+                }
+            }
+        }
+        false
+    }
+
+    /// Pieces of `color` that shield their king from an enemy slider, each
+    /// with the square of the piece pinning it.
+    pub fn pinned_pieces(&self, color: Color) -> Vec<(Position, Position)> {
+        let Some((king_row, king_col)) = self.find_king(color) else {
+            return Vec::new();
+        };
+        let mut pins = Vec::new();
+        for slider in [Piece::Rook, Piece::Bishop] {
+            for (di, dj) in slider.directions() {
+                let mut shield = None;
+                let (mut row, mut col) = (king_row as isize + di, king_col as isize + dj);
+                while on_board(row, col) {
+                    let square = self.squares[row as usize][col as usize];
+                    let pos = Position {
+                        row: row as usize,
+                        col: col as usize,
+                    };
+                    if square.color == Some(color) {
+                        if shield.is_some() {
+                            break;
+                        }
+                        shield = Some(pos);
+                    } else if square.color.is_some() {
+                        if let Some(shield) = shield {
+                            if square.piece == slider || square.piece == Piece::Queen {
+                                pins.push((shield, pos));
+                            }
+                        }
+                        break;
+                    }
+                    row += di;
+                    col += dj;
+                }
+            }
+        }
+        pins
+    }
+
+    fn find_king(&self, color: Color) -> Option<(usize, usize)> {
+        let king = Square::new(Piece::King, Some(color));
+        match self.kings[color_index(color)] {
+            Some(pos) if self.squares[pos.row][pos.col] == king => Some((pos.row, pos.col)),
+            // Captured in a pseudo-legal line
+            None => None,
+            // Stale after a direct edit of `squares`
+            Some(_) => self.scan_for_king(color),
+        }
+    }
+
+    fn scan_for_king(&self, color: Color) -> Option<(usize, usize)> {
+        self.pieces(color)
+            .find(|&(_, piece)| piece == Piece::King)
+            .map(|(pos, _)| (pos.row, pos.col))
+    }
+
+    /// Refreshes the cached king positions from `squares`.
+    pub(crate) fn locate_kings(&mut self) {
+        for color in [Color::White, Color::Black] {
+            self.kings[color_index(color)] = self
+                .scan_for_king(color)
+                .map(|(row, col)| Position { row, col });
+        }
+    }
+
+    pub(crate) fn hash(&self) -> u64 {
+        self.zobrist
+    }
+
+    /// Resets the running hash from the current position.
+    pub(crate) fn refresh_hash(&mut self) {
+        self.zobrist = self.compute_hash();
+    }
+
+    /// Hashes the position from scratch; `make_move` and `undo_move` keep
+    /// `zobrist` equal to this incrementally.
+    pub(crate) fn compute_hash(&self) -> u64 {
+        let keys = zobrist::keys();
+        let mut hash = 0;
+        for (i, row) in self.squares.iter().enumerate() {
+            for (j, square) in row.iter().enumerate() {
+                if let Some(color) = square.color {
+                    hash ^= keys.piece(square.piece, color, i, j);
+                }
+            }
+        }
+
+        if self.turn == Color::Black {
+            hash ^= keys.black_to_move;
+        }
+        hash ^= keys.castling_rights(self.castling);
+        if let Some(en_passant) = self.en_passant {
+            hash ^= keys.en_passant_file[en_passant.col];
+        }
+
+        hash
+    }
+
+    fn current_turn(&self) -> Color {
+        self.turn
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Clones start with an empty transposition table. The table only speeds up
+/// the search, and copying it on every lookahead costs far more than it saves.
+impl Clone for Board {
+    fn clone(&self) -> Self {
+        Board {
+            squares: self.squares,
+            transposition_table: TranspositionTable::new(self.transposition_table.capacity()),
+            turn: self.turn,
+            en_passant: self.en_passant,
+            castling: self.castling,
+            castling_king_col: self.castling_king_col,
+            castling_rook_cols: self.castling_rook_cols,
+            chess960: self.chess960,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            history: self.history.clone(),
+            position_counts: self.position_counts.clone(),
+            deadline: self.deadline,
+            stop: None,
+            completed_depth: self.completed_depth,
+            move_heuristics: self.move_heuristics,
+            mobility_term: self.mobility_term,
+            eval_params: self.eval_params,
+            contempt: self.contempt,
+            null_move: self.null_move,
+            late_move_reductions: self.late_move_reductions,
+            aspiration_windows: self.aspiration_windows,
+            seed: self.seed,
+            book: self.book.clone(),
+            null_plies: 0,
+            killers: Vec::new(),
+            history_scores: HashMap::new(),
+            root_ply: self.root_ply,
+            nodes: 0,
+            kings: self.kings,
+            zobrist: self.zobrist,
+        }
+    }
+}
+
+/// Puts together a position piece by piece, e.g.
+/// `Board::builder().place(Piece::King, Color::White, pos).build()`.
+pub struct BoardBuilder {
+    board: Board,
+}
+
+impl BoardBuilder {
+    pub fn place(mut self, piece: Piece, color: Color, pos: Position) -> Self {
+        self.board.squares[pos.row][pos.col] = Square::new(piece, Some(color));
+        self
+    }
+
+    pub fn turn(mut self, color: Color) -> Self {
+        self.board.turn = color;
+        self
+    }
+
+    pub fn castling(mut self, castling: CastlingRights) -> Self {
+        self.board.castling = castling;
+        self
+    }
+
+    pub fn build(mut self) -> Board {
+        self.board.locate_kings();
+        self.board.refresh_hash();
+        self.board
+    }
+}
+
+/// Boards are equal when they hold the same position: pieces, side to move,
+/// castling rights and en passant square. Move clocks, game history and
+/// search state, including the transposition table, are ignored.
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.squares == other.squares
+            && self.turn == other.turn
+            && self.castling == other.castling
+            && self.en_passant == other.en_passant
+            && self.castling_king_col == other.castling_king_col
+            && self.castling_rook_cols == other.castling_rook_cols
+            && self.chess960 == other.chess960
+    }
+}
+
+/// What a serialized `Board` holds: the position and move clocks, as in
+/// FEN, and where castling starts from. Search state, including the transposition table, and the game
+/// history are left out.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BoardState {
+    squares: [[Square; 8]; 8],
+    turn: Color,
+    castling: CastlingRights,
+    en_passant: Option<Position>,
+    halfmove_clock: usize,
+    fullmove_number: usize,
+    castling_king_col: usize,
+    castling_rook_cols: [usize; 2],
+    chess960: bool,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Board {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BoardState {
+            squares: self.squares,
+            turn: self.turn,
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            castling_king_col: self.castling_king_col,
+            castling_rook_cols: self.castling_rook_cols,
+            chess960: self.chess960,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Rejects states no position could have, such as a colorless piece or an
+/// en passant square off the third or sixth rank.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Board {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let state = BoardState::deserialize(deserializer)?;
+        let consistent = |square: &Square| (square.piece == Piece::Empty) == square.color.is_none();
+        if !state.squares.iter().flatten().all(consistent) {
+            return Err(D::Error::custom(
+                "a square has a piece without a color or vice versa",
+            ));
+        }
+        if state
+            .en_passant
+            .is_some_and(|pos| !pos.is_valid() || (pos.row != 2 && pos.row != 5))
+        {
+            return Err(D::Error::custom("invalid en passant square"));
+        }
+        if state.fullmove_number == 0 {
+            return Err(D::Error::custom("the fullmove number starts at 1"));
+        }
+        let [queenside_col, kingside_col] = state.castling_rook_cols;
+        if !(queenside_col < state.castling_king_col && state.castling_king_col < kingside_col)
+            || kingside_col >= 8
+        {
+            return Err(D::Error::custom(
+                "the castling rooks must start on either side of the king",
+            ));
+        }
+
+        let mut board = Board::empty();
+        board.squares = state.squares;
+        board.turn = state.turn;
+        board.castling = state.castling;
+        board.en_passant = state.en_passant;
+        board.halfmove_clock = state.halfmove_clock;
+        board.fullmove_number = state.fullmove_number;
+        board.castling_king_col = state.castling_king_col;
+        board.castling_rook_cols = state.castling_rook_cols;
+        board.chess960 = state.chess960;
+        board.locate_kings();
+        board.refresh_hash();
+        Ok(board)
+    }
+}
+
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Board({})", self.to_fen())
+    }
+}
+
+impl Board {
+    /// The board from White's side, rank 8 on top, with rank numbers down
+    /// the left and file letters underneath.
+    pub fn to_string_with_coords(&self) -> String {
+        let mut out = String::new();
+        self.write_grid(&mut out, false, true).unwrap();
+        out
+    }
+
+    /// The board as plain letters, rank 8 first: `KQRBNP` for White,
+    /// lowercase for Black and `.` for empty squares.
+    pub fn to_ascii(&self) -> String {
+        let mut out = String::new();
+        for row in self.squares.iter().rev() {
+            for square in row {
+                out.push(fen::piece_char(square).unwrap_or('.'));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Draws the board with rank 8 on top, or rank 1 with the files
+    /// reversed when `flipped`.
+    fn write_grid(&self, out: &mut impl fmt::Write, flipped: bool, coords: bool) -> fmt::Result {
+        let order = |i: usize| if flipped { i } else { 7 - i };
+        for i in 0..8 {
+            let row = order(i);
+            if coords {
+                write!(out, "{} ", row + 1)?;
+            }
+            for j in 0..8 {
+                write!(out, "{} ", self.squares[row][7 - order(j)])?;
+            }
+            writeln!(out)?;
+        }
+        if coords {
+            let mut files: Vec<char> = ('a'..='h').collect();
+            if flipped {
+                files.reverse();
+            }
+            write!(out, " ")?;
+            for file in files {
+                write!(out, " {file}")?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints the board from White's side; the alternate form (`{:#}`) prints
+/// it from Black's.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_grid(f, f.alternate(), false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        board::{
+            on_board, BISHOP_PAIR_BONUS, DOUBLED_PAWN_PENALTY, INFINITY, ISOLATED_PAWN_PENALTY,
+            MATE, MATE_THRESHOLD, MAX_DEPTH, MAX_QUIESCENCE_PLY, PASSED_PAWN_BONUS,
+            ROOK_OPEN_FILE_BONUS, ROOK_SEMI_OPEN_FILE_BONUS, TRAPPED_PIECE_PENALTY,
+        },
+        tt::{Bound, TtEntry},
+        Board, Color, DrawReason, EvalParams, GameResult, Move, MoveError, Piece, Position, Square,
+    };
+
+    #[test]
+    fn test_get_valid_moves_white_pawn_initial_position() {
+        let board = Board::new();
+        let moves = board.get_valid_moves(Color::White);
+
+        let expected_moves = vec![
+            Move {
+                from: Position { row: 1, col: 0 },
+                to: Position { row: 2, col: 0 },
+                piece: Piece::Pawn,
+                captured: None,
+                promotion: None,
+                score: 0,
+            },
+            Move {
+                from: Position { row: 1, col: 1 },
+                to: Position { row: 2, col: 1 },
+                piece: Piece::Pawn,
+                captured: None,
+                promotion: None,
+                score: 0,
+            },
+            Move {
+                from: Position { row: 1, col: 2 },
+                to: Position { row: 2, col: 2 },
+                piece: Piece::Pawn,
+                captured: None,
+                promotion: None,
+                score: 0,
+            },
+            Move {
+                from: Position { row: 1, col: 3 },
+                to: Position { row: 2, col: 3 },
+                piece: Piece::Pawn,
+                captured: None,
+                promotion: None,
+                score: 0,
+            },
+            Move {
+                from: Position { row: 1, col: 4 },
+                to: Position { row: 2, col: 4 },
                 piece: Piece::Pawn,
                 captured: None,
+                promotion: None,
                 score: 0,
             },
             Move {
@@ -571,6 +2738,7 @@ mod tests {
                 to: Position { row: 2, col: 5 },
                 piece: Piece::Pawn,
                 captured: None,
+                promotion: None,
                 score: 0,
             },
             Move {
@@ -578,6 +2746,7 @@ mod tests {
                 to: Position { row: 2, col: 6 },
                 piece: Piece::Pawn,
                 captured: None,
+                promotion: None,
                 score: 0,
             },
             Move {
@@ -585,6 +2754,7 @@ mod tests {
                 to: Position { row: 2, col: 7 },
                 piece: Piece::Pawn,
                 captured: None,
+                promotion: None,
                 score: 0,
             },
         ];
@@ -593,4 +2763,1819 @@ mod tests {
             assert!(moves.contains(&expected_move));
         }
     }
+
+    #[test]
+    fn test_get_valid_moves_white_pawn_diagonal_capture() {
+        let mut board = Board::new();
+        // White pawn d2-d4, Black pawn e7-e5
+        board.squares[1][3] = Square::new(Piece::Empty, None);
+        board.squares[3][3] = Square::new(Piece::Pawn, Some(Color::White));
+        board.squares[6][4] = Square::new(Piece::Empty, None);
+        board.squares[4][4] = Square::new(Piece::Pawn, Some(Color::Black));
+
+        let moves = board.get_valid_moves(Color::White);
+
+        let capture = Move {
+            from: Position { row: 3, col: 3 },
+            to: Position { row: 4, col: 4 },
+            piece: Piece::Pawn,
+            captured: Some(Piece::Pawn),
+            promotion: None,
+            score: 0,
+        };
+        assert!(moves.contains(&capture));
+        // No capture onto the empty c5 square
+        assert!(!moves
+            .iter()
+            .any(|mv| mv.from == (Position { row: 3, col: 3 }) && mv.to.col == 2));
+    }
+
+    #[test]
+    fn test_pawn_promotion() {
+        let mut board = Board::new();
+        board.squares = [[Square::new(Piece::Empty, None); 8]; 8];
+        board.squares[6][0] = Square::new(Piece::Pawn, Some(Color::White));
+
+        let moves = board.get_valid_moves(Color::White);
+        let promotions: Vec<Piece> = moves
+            .iter()
+            .filter(|mv| mv.to == (Position { row: 7, col: 0 }))
+            .filter_map(|mv| mv.promotion)
+            .collect();
+        assert_eq!(promotions.len(), 4);
+        for piece in [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight] {
+            assert!(promotions.contains(&piece));
+        }
+
+        let queen_promotion = *moves
+            .iter()
+            .find(|mv| mv.promotion == Some(Piece::Queen))
+            .unwrap();
+        board.make_move(queen_promotion);
+        assert_eq!(
+            board.squares[7][0],
+            Square::new(Piece::Queen, Some(Color::White))
+        );
+        assert_eq!(board.squares[6][0], Square::new(Piece::Empty, None));
+    }
+
+    fn find_move(board: &Board, from: Position, to: Position) -> Move {
+        *board
+            .get_valid_moves(board.turn)
+            .iter()
+            .find(|mv| mv.from == from && mv.to == to)
+            .expect("move should be available")
+    }
+
+    #[test]
+    fn test_en_passant_capture() {
+        let mut board = Board::new();
+        let sequence = [
+            ((1, 4), (3, 4)), // e4
+            ((6, 0), (5, 0)), // a6
+            ((3, 4), (4, 4)), // e5
+            ((6, 3), (4, 3)), // d5
+        ];
+        for ((from_row, from_col), (to_row, to_col)) in sequence {
+            let mv = find_move(
+                &board,
+                Position {
+                    row: from_row,
+                    col: from_col,
+                },
+                Position {
+                    row: to_row,
+                    col: to_col,
+                },
+            );
+            board.make_move(mv);
+        }
+        assert_eq!(board.en_passant, Some(Position { row: 5, col: 3 }));
+
+        // exd6 e.p.
+        let en_passant = find_move(
+            &board,
+            Position { row: 4, col: 4 },
+            Position { row: 5, col: 3 },
+        );
+        assert_eq!(en_passant.captured, Some(Piece::Pawn));
+        board.make_move(en_passant);
+
+        assert_eq!(
+            board.squares[5][3],
+            Square::new(Piece::Pawn, Some(Color::White))
+        );
+        assert_eq!(board.squares[4][3], Square::new(Piece::Empty, None));
+        assert_eq!(board.squares[4][4], Square::new(Piece::Empty, None));
+        assert_eq!(board.en_passant, None);
+    }
+
+    #[test]
+    fn test_kingside_castling() {
+        let mut board = Board::new();
+        board.squares[0][5] = Square::new(Piece::Empty, None);
+        board.squares[0][6] = Square::new(Piece::Empty, None);
+
+        let castle = find_move(
+            &board,
+            Position { row: 0, col: 4 },
+            Position { row: 0, col: 6 },
+        );
+        board.make_move(castle);
+
+        assert_eq!(
+            board.squares[0][6],
+            Square::new(Piece::King, Some(Color::White))
+        );
+        assert_eq!(
+            board.squares[0][5],
+            Square::new(Piece::Rook, Some(Color::White))
+        );
+        assert_eq!(board.squares[0][4], Square::new(Piece::Empty, None));
+        assert_eq!(board.squares[0][7], Square::new(Piece::Empty, None));
+        assert!(!board.castling.white_kingside);
+        assert!(!board.castling.white_queenside);
+        assert!(board.castling.black_kingside);
+    }
+
+    #[test]
+    fn test_chess960_start_positions() {
+        let standard = Board::from_chess960(518);
+        assert_eq!(standard.squares, Board::new().squares);
+        assert_eq!(standard.castling, Board::new().castling);
+        // Castling is written as e1h1 rather than e1g1, so the moves differ
+        assert_ne!(standard, Board::new());
+        assert_eq!(
+            standard.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+        );
+
+        let board = Board::from_chess960(0);
+        assert_eq!(
+            board.to_fen(),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1"
+        );
+        assert_eq!(board.get_legal_moves(Color::White).len(), 20);
+    }
+
+    #[test]
+    fn test_chess960_fen_round_trip() {
+        let mut board = Board::from_chess960(217);
+        board
+            .apply_san_line("1. Nb3 e5 2. Nh3 f5 3. f4 exf4 4. Nxf4 Nh6")
+            .unwrap();
+        assert_eq!(
+            board.to_fen(),
+            "nqrbbk1r/pppp2pp/7n/5p2/5N2/1N6/PPPPP1PP/1QRBBK1R w HChc - 1 5"
+        );
+        let restored = Board::from_fen(&board.to_fen()).unwrap();
+        assert_eq!(restored.to_fen(), board.to_fen());
+        let uci = |board: &Board| -> Vec<String> {
+            board
+                .get_legal_moves(board.turn)
+                .iter()
+                .map(|mv| board.move_to_uci(mv))
+                .collect()
+        };
+        assert_eq!(uci(&restored), uci(&board));
+        assert!(restored.parse_san("O-O").is_ok());
+        assert_eq!(restored, board);
+    }
+
+    #[test]
+    fn test_chess960_castling() {
+        // King on g1 between rooks on f1 and h1
+        let mut board = Board::from_chess960(0);
+        for col in 0..5 {
+            board.squares[0][col] = Square::new(Piece::Empty, None);
+        }
+        board.refresh_hash();
+        let before = board.clone();
+
+        assert!(board.parse_san("O-O").is_err(), "the f1 rook is in the way");
+        let castle = board.parse_san("O-O-O").unwrap();
+        assert_eq!(board.move_to_uci(&castle), "g1f1");
+        assert_eq!(board.move_to_san(&castle), "O-O-O");
+        board.make_move(castle);
+        let white = |piece| Square::new(piece, Some(Color::White));
+        assert_eq!(board.squares[0][2], white(Piece::King));
+        assert_eq!(board.squares[0][3], white(Piece::Rook));
+        assert_eq!(board.squares[0][5].piece, Piece::Empty);
+        assert_eq!(board.squares[0][6].piece, Piece::Empty);
+        assert!(!board.castling.white_kingside && !board.castling.white_queenside);
+        assert_eq!(board.hash(), board.compute_hash());
+        board.undo_move();
+        assert_eq!(board, before);
+
+        // Castling kingside from g1 leaves the king where it is
+        board.squares[0][5] = Square::new(Piece::Empty, None);
+        board.refresh_hash();
+        let castle = board.parse_san("O-O").unwrap();
+        assert_eq!(board.move_to_uci(&castle), "g1h1");
+        board.make_move(castle);
+        assert_eq!(board.squares[0][6], white(Piece::King));
+        assert_eq!(board.squares[0][5], white(Piece::Rook));
+        assert_eq!(board.squares[0][7].piece, Piece::Empty);
+        assert!(!board.is_in_check(Color::White));
+        assert_eq!(board.hash(), board.compute_hash());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut board = Board::new();
+        board.apply_san_line("1. e4 c5 2. Nf3 d6 3. d4").unwrap();
+        let json = serde_json::to_string(&board).unwrap();
+        let restored: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, board);
+        assert_eq!(restored.to_fen(), board.to_fen());
+        assert_eq!(restored.hash(), board.hash());
+        assert_eq!(
+            restored.get_legal_moves(Color::Black),
+            board.get_legal_moves(Color::Black)
+        );
+
+        let mv = board.parse_san("cxd4").unwrap();
+        let json = serde_json::to_string(&mv).unwrap();
+        assert_eq!(serde_json::from_str::<Move>(&json).unwrap(), mv);
+
+        // A pawn with no color
+        let mut broken = serde_json::to_value(&board).unwrap();
+        broken["squares"][1][0]["color"] = serde_json::Value::Null;
+        assert!(serde_json::from_value::<Board>(broken).is_err());
+
+        let mut board = Board::from_chess960(0);
+        board.apply_san_line("1. e4 e5 2. Ne3 Ne6").unwrap();
+        let json = serde_json::to_string(&board).unwrap();
+        let restored: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, board);
+        assert_eq!(restored.to_fen(), board.to_fen());
+        let uci = |board: &Board| -> Vec<String> {
+            board
+                .get_legal_moves(board.turn)
+                .iter()
+                .map(|mv| board.move_to_uci(mv))
+                .collect()
+        };
+        assert_eq!(uci(&restored), uci(&board));
+
+        // The king outside its rooks
+        let mut broken = serde_json::to_value(&board).unwrap();
+        broken["castling_king_col"] = 7.into();
+        assert!(serde_json::from_value::<Board>(broken).is_err());
+    }
+
+    #[test]
+    fn test_set_turn_clears_en_passant() {
+        let mut board = Board::new();
+        board.apply_san_line("1. e4").unwrap();
+        assert_eq!(board.en_passant, Position::from_algebraic("e3"));
+
+        board.set_turn(Color::Black);
+        assert_eq!(board.en_passant, Position::from_algebraic("e3"));
+
+        board.set_turn(Color::White);
+        assert_eq!(board.turn, Color::White);
+        assert_eq!(board.en_passant, None);
+        assert_eq!(board.hash(), board.compute_hash());
+    }
+
+    #[test]
+    fn test_mirror() {
+        let board = Board::from_fen("4k3/8/8/3pP3/8/8/8/R3K3 w Q d6 0 5").unwrap();
+        let mirrored = board.mirror();
+        assert_eq!(mirrored.to_fen(), "r3k3/8/8/8/3Pp3/8/8/4K3 b q d3 0 5");
+        assert_eq!(mirrored.mirror(), board);
+    }
+
+    #[test]
+    fn test_evaluation_is_color_symmetric() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r1bqk2r/ppp2ppp/2np1n2/2b1p3/2B1P3/2NP1N2/PPP2PPP/R1BQK2R w KQkq - 0 6",
+            "r4rk1/pp3ppp/2n5/3q4/3P4/2P2N2/P4PPP/R2Q1RK1 w - - 0 15",
+            "8/5pk1/6p1/3P4/1p6/1P3K2/8/8 b - - 0 40",
+            "2kr3r/ppp2ppp/2n5/8/1b6/2N1B3/PPP2PPP/2KR3R w - - 0 12",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(
+                board.evaluate_board(),
+                -board.mirror().evaluate_board(),
+                "{}",
+                fen
+            );
+            assert_eq!(
+                board.evaluate_relative(),
+                board.mirror().evaluate_relative(),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_castling_through_check_forbidden() {
+        let mut board = Board::new();
+        board.squares[0][5] = Square::new(Piece::Empty, None);
+        board.squares[0][6] = Square::new(Piece::Empty, None);
+        // Open the f-file and aim a Black rook at f1
+        board.squares[1][5] = Square::new(Piece::Empty, None);
+        board.squares[3][5] = Square::new(Piece::Rook, Some(Color::Black));
+
+        let moves = board.get_valid_moves(Color::White);
+        assert!(!moves.iter().any(|mv| mv.piece == Piece::King
+            && mv.from == (Position { row: 0, col: 4 })
+            && mv.to == (Position { row: 0, col: 6 })));
+    }
+
+    #[test]
+    fn test_pinned_knight_cannot_move() {
+        let mut board = Board::new();
+        board.squares = [[Square::new(Piece::Empty, None); 8]; 8];
+        board.squares[0][4] = Square::new(Piece::King, Some(Color::White));
+        board.squares[1][4] = Square::new(Piece::Knight, Some(Color::White));
+        board.squares[7][4] = Square::new(Piece::Rook, Some(Color::Black));
+        board.squares[7][0] = Square::new(Piece::King, Some(Color::Black));
+
+        let pseudo_legal = board.get_valid_moves(Color::White);
+        assert!(pseudo_legal.iter().any(|mv| mv.piece == Piece::Knight));
+
+        let legal = board.get_legal_moves(Color::White);
+        assert!(!legal.is_empty());
+        assert!(!legal.iter().any(|mv| mv.piece == Piece::Knight));
+    }
+
+    #[test]
+    fn test_hash_is_independent_of_move_order() {
+        let knight_f3 = (Position { row: 0, col: 6 }, Position { row: 2, col: 5 });
+        let knight_c3 = (Position { row: 0, col: 1 }, Position { row: 2, col: 2 });
+        let knight_f6 = (Position { row: 7, col: 6 }, Position { row: 5, col: 5 });
+
+        let mut first = Board::new();
+        let mut second = Board::new();
+        let initial_hash = first.hash();
+        for (from, to) in [knight_f3, knight_f6, knight_c3] {
+            let mv = find_move(&first, from, to);
+            first.make_move(mv);
+        }
+        for (from, to) in [knight_c3, knight_f6, knight_f3] {
+            let mv = find_move(&second, from, to);
+            second.make_move(mv);
+        }
+        assert_eq!(first.hash(), second.hash());
+
+        let mut board = Board::new();
+        let (from, to) = knight_f3;
+        board.make_move(find_move(&board, from, to));
+        assert_ne!(board.hash(), initial_hash);
+    }
+
+    #[test]
+    fn test_castling_rights_distinguish_positions() {
+        let full = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let partial = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1").unwrap();
+        assert_eq!(full.squares, partial.squares);
+        assert_ne!(full.to_fen(), partial.to_fen());
+        assert_ne!(full.hash(), partial.hash());
+
+        // Moving the rook out and back loses the right for good
+        let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        board.apply_san_line("Rh2 Rh7 Rh1 Rh8").unwrap();
+        assert_eq!(board.squares, full.squares);
+        assert_eq!(board.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w Qq - 4 3");
+        assert_ne!(board.hash(), full.hash());
+        assert!(!board.is_threefold_repetition());
+    }
+
+    #[test]
+    fn test_incremental_hash_matches_full_hash() {
+        use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        // Castling rights and en passant squares both change along the way
+        let mut board =
+            Board::from_fen("r3k2r/pppq1ppp/2n2n2/3pp3/3PP3/2N2N2/PPPQ1PPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let mut played = 0;
+        for _ in 0..200 {
+            let moves = board.get_legal_moves(board.turn);
+            let Some(&mv) = moves.choose(&mut rng) else {
+                break;
+            };
+            board.make_move(mv);
+            played += 1;
+            assert_eq!(board.hash(), board.compute_hash(), "after {mv:?}");
+
+            let en_passant = board.make_null_move();
+            assert_eq!(board.hash(), board.compute_hash());
+            board.undo_null_move(en_passant);
+        }
+        for _ in 0..played {
+            board.undo_move();
+            assert_eq!(board.hash(), board.compute_hash());
+        }
+    }
+
+    #[test]
+    fn test_quiescence_uses_side_to_move() {
+        let mut board = Board::new();
+        // Both sides have a pawn capture available: d4xe5 and e5xd4
+        board.squares[1][3] = Square::new(Piece::Empty, None);
+        board.squares[3][3] = Square::new(Piece::Pawn, Some(Color::White));
+        board.squares[6][4] = Square::new(Piece::Empty, None);
+        board.squares[4][4] = Square::new(Piece::Pawn, Some(Color::Black));
+        board.turn = Color::Black;
+
+        let moves = board.quiescence_moves();
+        assert!(!moves.is_empty());
+        for mv in moves {
+            assert_eq!(
+                board.squares[mv.from.row][mv.from.col].color,
+                Some(Color::Black)
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_make_move_rejects_wrong_color() {
+        let mut board = Board::new();
+        let black_push = Move {
+            from: Position { row: 6, col: 4 },
+            to: Position { row: 4, col: 4 },
+            piece: Piece::Pawn,
+            captured: None,
+            promotion: None,
+            score: 0,
+        };
+        assert_eq!(
+            board.try_make_move(black_push),
+            Err(MoveError::WrongColor(black_push.from))
+        );
+        assert_eq!(board.turn, Color::White);
+    }
+
+    #[test]
+    fn test_try_make_move_rejects_empty_square() {
+        let mut board = Board::new();
+        let from_empty = Move {
+            from: Position { row: 3, col: 4 },
+            to: Position { row: 4, col: 4 },
+            piece: Piece::Pawn,
+            captured: None,
+            promotion: None,
+            score: 0,
+        };
+        assert_eq!(
+            board.try_make_move(from_empty),
+            Err(MoveError::EmptySquare(from_empty.from))
+        );
+
+        let legal = find_move(
+            &board,
+            Position { row: 1, col: 4 },
+            Position { row: 3, col: 4 },
+        );
+        assert_eq!(board.try_make_move(legal), Ok(()));
+        assert_eq!(board.turn, Color::Black);
+    }
+
+    #[test]
+    fn test_off_board_positions_are_rejected() {
+        let mut board = Board::new();
+        let off_board = Move {
+            from: Position { row: 1, col: 4 },
+            to: Position { row: 1, col: 8 },
+            piece: Piece::Pawn,
+            captured: None,
+            promotion: None,
+            score: 0,
+        };
+        assert_eq!(
+            board.try_make_move(off_board),
+            Err(MoveError::OffBoard(off_board.to))
+        );
+        assert!(board
+            .legal_moves_from(Position { row: 8, col: 0 })
+            .is_empty());
+    }
+
+    #[test]
+    fn test_undo_move_restores_initial_position() {
+        let mut board = Board::new();
+        let sequence = [
+            ((1, 4), (3, 4)), // e4
+            ((6, 3), (4, 3)), // d5
+            ((3, 4), (4, 3)), // exd5
+            ((6, 2), (5, 2)), // c6
+            ((4, 3), (5, 2)), // dxc6
+            ((7, 6), (5, 5)), // Nf6
+            ((5, 2), (6, 1)), // cxb7
+            ((6, 4), (5, 4)), // e6
+        ];
+        for ((from_row, from_col), (to_row, to_col)) in sequence {
+            let mv = find_move(
+                &board,
+                Position {
+                    row: from_row,
+                    col: from_col,
+                },
+                Position {
+                    row: to_row,
+                    col: to_col,
+                },
+            );
+            board.make_move(mv);
+        }
+
+        // bxa8=Q
+        let promotion = *board
+            .get_valid_moves(Color::White)
+            .iter()
+            .find(|mv| mv.to == (Position { row: 7, col: 0 }) && mv.promotion == Some(Piece::Queen))
+            .unwrap();
+        board.make_move(promotion);
+        assert_eq!(
+            board.squares[7][0],
+            Square::new(Piece::Queen, Some(Color::White))
+        );
+
+        assert_eq!(board.undo_move(), Some(promotion));
+        assert_eq!(
+            board.squares[6][1],
+            Square::new(Piece::Pawn, Some(Color::White))
+        );
+        assert_eq!(
+            board.squares[7][0],
+            Square::new(Piece::Rook, Some(Color::Black))
+        );
+        while board.undo_move().is_some() {}
+
+        assert_eq!(board, Board::new());
+    }
+
+    #[test]
+    fn test_undo_castling_and_en_passant() {
+        let mut board = Board::new();
+        board.squares[0][5] = Square::new(Piece::Empty, None);
+        board.squares[0][6] = Square::new(Piece::Empty, None);
+        board.squares[4][4] = Square::new(Piece::Pawn, Some(Color::White));
+        board.squares[1][4] = Square::new(Piece::Empty, None);
+        let before = board.clone();
+
+        board.make_move(find_move(
+            &board,
+            Position { row: 0, col: 4 },
+            Position { row: 0, col: 6 },
+        ));
+        board.make_move(find_move(
+            &board,
+            Position { row: 6, col: 3 },
+            Position { row: 4, col: 3 },
+        ));
+        let en_passant_board = board.clone();
+        board.make_move(find_move(
+            &board,
+            Position { row: 4, col: 4 },
+            Position { row: 5, col: 3 },
+        ));
+
+        board.undo_move();
+        assert_eq!(board.squares, en_passant_board.squares);
+        assert_eq!(board.en_passant, en_passant_board.en_passant);
+        board.undo_move();
+        board.undo_move();
+        assert_eq!(board.squares, before.squares);
+        assert_eq!(board.castling, before.castling);
+        assert_eq!(board.turn, before.turn);
+    }
+
+    #[test]
+    fn test_fifty_move_draw() {
+        let mut board = Board::new();
+        board.halfmove_clock = 98;
+        board.make_move(find_move(
+            &board,
+            Position { row: 0, col: 6 },
+            Position { row: 2, col: 5 },
+        ));
+        assert_eq!(board.halfmove_clock, 99);
+        assert!(!board.is_fifty_move_draw());
+
+        board.make_move(find_move(
+            &board,
+            Position { row: 7, col: 6 },
+            Position { row: 5, col: 5 },
+        ));
+        assert_eq!(board.halfmove_clock, 100);
+        assert!(board.is_fifty_move_draw());
+
+        board.undo_move();
+        assert_eq!(board.halfmove_clock, 99);
+
+        // A pawn move resets the clock
+        board.make_move(find_move(
+            &board,
+            Position { row: 6, col: 4 },
+            Position { row: 4, col: 4 },
+        ));
+        assert_eq!(board.halfmove_clock, 0);
+        assert!(!board.is_fifty_move_draw());
+    }
+
+    #[test]
+    fn test_threefold_repetition() {
+        let mut board = Board::new();
+        let knight_shuffle = [
+            ((0, 6), (2, 5)), // Nf3
+            ((7, 6), (5, 5)), // Nf6
+            ((2, 5), (0, 6)), // Ng1
+            ((5, 5), (7, 6)), // Ng8
+        ];
+
+        for round in 0..2 {
+            assert!(!board.is_threefold_repetition(), "round {}", round);
+            for ((from_row, from_col), (to_row, to_col)) in knight_shuffle {
+                let mv = find_move(
+                    &board,
+                    Position {
+                        row: from_row,
+                        col: from_col,
+                    },
+                    Position {
+                        row: to_row,
+                        col: to_col,
+                    },
+                );
+                board.make_move(mv);
+            }
+        }
+        assert!(board.is_threefold_repetition());
+
+        board.undo_move();
+        assert!(!board.is_threefold_repetition());
+    }
+
+    #[test]
+    fn test_contempt_avoids_repetition() {
+        // Material is level, and Black can repeat with Nc6 as the knights
+        // already went out and back
+        let mut board = Board::new();
+        board
+            .apply_san_line("1. e4 e5 2. Nf3 Nc6 3. Ng1 Nb8 4. Nf3")
+            .unwrap();
+        let mut contemptuous = board.clone();
+
+        let drawn = board.search(4);
+        assert_eq!(board.move_to_san(&drawn.best_move.unwrap()), "Nc6");
+        assert_eq!(drawn.score, 0);
+
+        // Rather be slightly worse than draw
+        contemptuous.set_contempt(20);
+        let result = contemptuous.search(4);
+        assert_ne!(contemptuous.move_to_san(&result.best_move.unwrap()), "Nc6");
+        assert!(result.score > -20 && result.score < 0, "{}", result.score);
+    }
+
+    #[test]
+    fn test_fifty_move_mate_beats_the_draw() {
+        // Ra8# is the hundredth halfmove, but mate still wins
+        let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80").unwrap();
+        let result = board.search(2);
+        let mate = result.best_move.unwrap();
+        assert_eq!(board.move_to_san(&mate), "Ra8#");
+        assert!(result.score >= MATE_THRESHOLD, "{}", result.score);
+        board.make_move(mate);
+        assert_eq!(
+            board.result(),
+            GameResult::Checkmate {
+                winner: Color::White
+            }
+        );
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",    // K vs K
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",  // K+B vs K
+            "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1",  // K+N vs K
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", // same-colored bishops
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert!(board.is_insufficient_material(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_sufficient_material() {
+        for fen in [
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",   // K+P vs K
+            "4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1", // opposite-colored bishops
+            "4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1",  // K+B+N vs K
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            assert!(!board.is_insufficient_material(), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_result_stalemate() {
+        let board = Board::from_fen("k7/8/1Q6/8/8/8/8/2K5 b - - 0 1").unwrap();
+        assert!(board.is_stalemate(Color::Black));
+        assert!(!board.is_checkmate(Color::Black));
+        assert_eq!(board.result(), GameResult::Stalemate);
+    }
+
+    #[test]
+    fn test_result_checkmate() {
+        let board = Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert!(!board.is_stalemate(Color::Black));
+        assert_eq!(
+            board.result(),
+            GameResult::Checkmate {
+                winner: Color::White
+            }
+        );
+        assert_eq!(Board::new().result(), GameResult::Ongoing);
+    }
+
+    #[test]
+    fn test_is_game_over() {
+        assert_eq!(Board::new().is_game_over(), None);
+
+        let king_and_bishop = Board::from_fen("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1").unwrap();
+        assert_eq!(
+            king_and_bishop.is_game_over(),
+            Some(GameResult::Draw {
+                reason: DrawReason::InsufficientMaterial
+            })
+        );
+
+        let stale = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w - - 100 80").unwrap();
+        assert_eq!(
+            stale.is_game_over(),
+            Some(GameResult::Draw {
+                reason: DrawReason::FiftyMoveRule
+            })
+        );
+    }
+
+    #[test]
+    fn test_find_best_move_mates_in_one_for_either_color() {
+        let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let best = board.find_best_move().unwrap();
+        assert_eq!(best.from, Position { row: 0, col: 0 });
+        assert_eq!(best.to, Position { row: 7, col: 0 });
+
+        let mut board = Board::from_fen("r5k1/8/8/8/8/8/5PPP/6K1 b - - 0 1").unwrap();
+        let best = board.find_best_move().unwrap();
+        assert_eq!(best.from, Position { row: 7, col: 0 });
+        assert_eq!(best.to, Position { row: 0, col: 0 });
+    }
+
+    #[test]
+    fn test_find_best_move_timed() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+
+        let hurried = board.find_best_move_timed(Duration::ZERO).unwrap();
+        assert!(board.get_legal_moves(Color::White).contains(&hurried));
+        let hurried_depth = board.completed_depth;
+
+        let relaxed = board
+            .find_best_move_timed(Duration::from_millis(300))
+            .unwrap();
+        assert!(board.get_legal_moves(Color::White).contains(&relaxed));
+        assert!(board.completed_depth > hurried_depth);
+        assert!(board.deadline.is_none());
+    }
+
+    #[test]
+    fn test_find_best_move_parallel() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        let mv = board.find_best_move_parallel(4).unwrap();
+        assert!(board.get_legal_moves(Color::White).contains(&mv));
+        assert_eq!(
+            board,
+            Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap()
+        );
+
+        // Back-rank mate
+        let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let mate = board.find_best_move_parallel(3).unwrap();
+        assert_eq!(board.move_to_uci(&mate), "a1a8");
+    }
+
+    #[test]
+    fn test_top_moves() {
+        let mut board = Board::new();
+        let top = board.top_moves(1, 3);
+        assert_eq!(top.len(), 3);
+        let legal = board.get_legal_moves(Color::White);
+        for (i, &(mv, score)) in top.iter().enumerate() {
+            assert!(legal.contains(&mv));
+            assert!(top[..i].iter().all(|&(other, _)| other != mv));
+            assert!(top[..i].iter().all(|&(_, better)| better >= score));
+        }
+        assert_eq!(top[0].1, board.search(1).score);
+        assert_eq!(board, Board::new());
+
+        // Fewer moves than asked for
+        let mut board = Board::from_fen("7k/8/5Q2/8/8/8/8/K7 b - - 0 1").unwrap();
+        assert_eq!(board.top_moves(1, 5).len(), 2);
+        assert!(board.top_moves(0, 5).is_empty());
+    }
+
+    #[test]
+    fn test_top_move_scores_like_search() {
+        // A full-window search of every root move scores all but the first
+        // of these differently from `search`
+        for fen in [
+            "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+            "rn2qbr1/p3pp2/b2k2pp/2pP1P2/3P1K1P/R7/1P1BP1BN/3RQ2n b - - 0 30",
+            "rnb4r/pp1pbkpp/7n/q3ppPP/2p2P2/N6B/PPPPP3/R1BQK1NR b KQ - 2 9",
+            "2b5/1qpk1pr1/4n1p1/p6p/1P1Br1Pb/7P/P2PPNB1/R2K2R1 b - - 1 37",
+        ] {
+            let top = Board::from_fen(fen).unwrap().top_moves(2, 1);
+            let searched = Board::from_fen(fen).unwrap().search(2);
+            assert_eq!(
+                top,
+                vec![(searched.best_move.unwrap(), searched.score)],
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_search_principal_variation() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        let result = board.search(MAX_DEPTH);
+        assert_eq!(result.pv.first().copied(), result.best_move);
+        assert_eq!(result.best_move, board.find_best_move());
+
+        let mut line = board.clone();
+        for mv in &result.pv {
+            assert!(line.get_legal_moves(line.turn).contains(mv));
+            line.make_move(*mv);
+        }
+        assert!(result.pv.len() > 1);
+    }
+
+    #[test]
+    fn test_perft_initial_position() {
+        let mut board = Board::new();
+        assert_eq!(board.perft(1), 20);
+        assert_eq!(board.perft(2), 400);
+        assert_eq!(board.squares, Board::new().squares);
+    }
+
+    #[test]
+    fn test_perft_divide_sums_to_perft() {
+        let mut board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let split = board.perft_divide(2);
+        assert_eq!(split.len(), 48);
+        let total: u64 = split.iter().map(|(_, nodes)| nodes).sum();
+        assert_eq!(total, board.perft(2));
+
+        let sans: Vec<String> = split.iter().map(|(mv, _)| board.move_to_san(mv)).collect();
+        let mut sorted = sans.clone();
+        sorted.sort();
+        assert_eq!(sans, sorted);
+    }
+
+    /// Plain negamax over cloned boards, without pruning or caching.
+    fn reference_negamax(board: &Board, depth: usize) -> i32 {
+        if depth == 0 {
+            return board.clone().quiescence_search(-INFINITY, INFINITY, 0);
+        }
+        let mut best = -INFINITY;
+        for mv in board.get_legal_moves(board.turn) {
+            let mut child = board.clone();
+            child.make_move(mv);
+            best = best.max(-reference_negamax(&child, depth - 1));
+        }
+        best
+    }
+
+    #[test]
+    fn test_make_unmake_search_matches_cloning_search() {
+        for fen in [
+            "r3k3/1p3p2/8/3n4/8/2B5/5PP1/4K2R w Kq - 0 1",
+            "4k3/8/3q4/8/3N4/8/4P3/4K3 w - - 0 1",
+        ] {
+            let mut board = Board::from_fen(fen).unwrap();
+            let result = board.search(1);
+            // The root searches each child one ply deeper than `depth`
+            assert_eq!(result.score, reference_negamax(&board, 2), "{}", fen);
+            assert_eq!(board.to_fen(), fen);
+            assert!(board.history.is_empty());
+            assert!(!board.transposition_table.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_clone_does_not_copy_transposition_table() {
+        let mut board = Board::new();
+        board.search(1);
+        assert!(!board.transposition_table.is_empty());
+
+        let copy = board.clone();
+        assert!(copy.transposition_table.is_empty());
+        assert_eq!(copy.to_fen(), board.to_fen());
+    }
+
+    #[test]
+    fn test_mvv_lva_orders_queen_capture_before_pawn_capture() {
+        let board = Board::from_fen("4k3/8/8/3q2p1/5P2/8/8/3QK3 w - - 0 1").unwrap();
+        let moves = board.get_legal_moves(Color::White);
+        let index_of = |from: Position, to: Position| {
+            moves
+                .iter()
+                .position(|mv| mv.from == from && mv.to == to)
+                .unwrap()
+        };
+
+        let queen_takes_queen = index_of(Position { row: 0, col: 3 }, Position { row: 4, col: 3 });
+        let pawn_takes_pawn = index_of(Position { row: 3, col: 5 }, Position { row: 4, col: 6 });
+        assert_eq!(queen_takes_queen, 0);
+        assert!(queen_takes_queen < pawn_takes_pawn);
+        assert!(moves[..=pawn_takes_pawn].iter().all(|mv| mv.is_capture()));
+    }
+
+    #[test]
+    fn test_trapped_knight() {
+        // On a8 the knight can only take the king-defended pawn or step
+        // where the pawn takes it
+        let trapped = Board::from_fen("N2k4/2p5/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let active = Board::from_fen("3k4/2p5/8/8/4N3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(trapped.trapped_minors(Color::White), 1);
+        assert_eq!(active.trapped_minors(Color::White), 0);
+        assert_eq!(
+            active.evaluate_piece_activity() - trapped.evaluate_piece_activity(),
+            TRAPPED_PIECE_PENALTY
+        );
+        assert!(trapped.evaluate_board() < active.evaluate_board());
+
+        // Boxed in by its own pawns is blocked, not trapped
+        assert_eq!(Board::new().trapped_minors(Color::White), 0);
+    }
+
+    #[test]
+    fn test_see() {
+        // The knight is defended by a pawn, so the queen is lost for it
+        let board = Board::from_fen("4k3/8/2p5/3n4/8/8/8/3QK3 w - - 0 1").unwrap();
+        let queen_takes = find_move(
+            &board,
+            Position { row: 0, col: 3 },
+            Position { row: 4, col: 3 },
+        );
+        assert_eq!(board.see(&queen_takes), 30 - 90);
+
+        let board = Board::from_fen("4k3/8/8/3n4/8/8/8/3QK3 w - - 0 1").unwrap();
+        let queen_takes = find_move(
+            &board,
+            Position { row: 0, col: 3 },
+            Position { row: 4, col: 3 },
+        );
+        assert_eq!(board.see(&queen_takes), 30);
+
+        // The second rook backs up the first through it
+        let board = Board::from_fen("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1").unwrap();
+        let rook_takes = find_move(
+            &board,
+            Position { row: 1, col: 3 },
+            Position { row: 4, col: 3 },
+        );
+        assert_eq!(board.see(&rook_takes), 10);
+
+        // Stepping onto a square a pawn guards
+        let board = Board::from_fen("4k3/8/2p5/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let queen_steps = find_move(
+            &board,
+            Position { row: 0, col: 3 },
+            Position { row: 4, col: 3 },
+        );
+        assert_eq!(board.see(&queen_steps), -90);
+    }
+
+    #[test]
+    fn test_move_heuristics_reduce_nodes() {
+        let fen = "r4rk1/pp3ppp/2n5/3q4/3P4/2P2N2/P4PPP/R2Q1RK1 w - - 0 15";
+        let mut plain = Board::from_fen(fen).unwrap();
+        plain.move_heuristics = false;
+        let plain_result = plain.search(2);
+
+        let mut ordered = Board::from_fen(fen).unwrap();
+        let ordered_result = ordered.search(2);
+
+        assert_eq!(ordered_result.score, plain_result.score);
+        assert!(
+            ordered.nodes < plain.nodes,
+            "{} >= {}",
+            ordered.nodes,
+            plain.nodes
+        );
+    }
+
+    #[test]
+    fn test_shallow_tt_entry_does_not_cut_deeper_search() {
+        let fen = "r3k3/1p3p2/8/3n4/8/2B5/5PP1/4K2R w Kq - 0 1";
+        let expected = Board::from_fen(fen).unwrap().search(1).score;
+
+        // Poison every child position with a bogus depth 0 score
+        let mut board = Board::from_fen(fen).unwrap();
+        for mv in board.get_legal_moves(board.turn) {
+            board.make_move(mv);
+            let entry = TtEntry {
+                depth: 0,
+                score: INFINITY / 2,
+                flag: Bound::Exact,
+                best_move: None,
+            };
+            board.transposition_table.insert(board.hash(), entry);
+            board.undo_move();
+        }
+
+        assert_eq!(board.search(1).score, expected);
+    }
+
+    #[test]
+    fn test_knight_moves_from_center_and_corner() {
+        let count_from = |fen: &str, from: Position| {
+            let board = Board::from_fen(fen).unwrap();
+            board
+                .get_valid_moves(Color::White)
+                .iter()
+                .filter(|mv| mv.from == from)
+                .count()
+        };
+
+        assert_eq!(
+            count_from(
+                "4k3/8/8/8/3N4/8/8/4K3 w - - 0 1",
+                Position { row: 3, col: 3 }
+            ),
+            8
+        );
+        assert_eq!(
+            count_from(
+                "4k3/8/8/8/8/8/8/N3K3 w - - 0 1",
+                Position { row: 0, col: 0 }
+            ),
+            2
+        );
+    }
+
+    #[test]
+    fn test_king_moves_from_center_and_corner() {
+        let count_from = |fen: &str, from: Position| {
+            let board = Board::from_fen(fen).unwrap();
+            board
+                .get_valid_moves(Color::White)
+                .iter()
+                .filter(|mv| mv.from == from)
+                .count()
+        };
+
+        assert_eq!(
+            count_from("4k3/8/8/8/3K4/8/8/8 w - - 0 1", Position { row: 3, col: 3 }),
+            8
+        );
+        assert_eq!(
+            count_from("4k3/8/8/8/8/8/8/K7 w - - 0 1", Position { row: 0, col: 0 }),
+            3
+        );
+        // Friendly pieces block the king
+        assert_eq!(
+            count_from(
+                "4k3/8/8/8/8/8/PP6/K7 w - - 0 1",
+                Position { row: 0, col: 0 }
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn test_piece_square_tables_are_mirrored() {
+        let white_pawn = Square::new(Piece::Pawn, Some(Color::White));
+        let black_pawn = Square::new(Piece::Pawn, Some(Color::Black));
+        assert_eq!(
+            Board::piece_square_value(white_pawn, 5, 2, 1.0),
+            Board::piece_square_value(black_pawn, 2, 2, 1.0)
+        );
+
+        let white = Board::from_fen("4k3/8/2P5/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let black = Board::from_fen("4k3/8/8/8/8/2p5/8/4K3 w - - 0 1").unwrap();
+        assert!(white.evaluate_board() > 0);
+        assert_eq!(white.evaluate_board(), -black.evaluate_board());
+    }
+
+    #[test]
+    fn test_game_phase() {
+        assert_eq!(Board::new().game_phase(), 1.0);
+        let pawns = Board::from_fen("4k3/pp6/8/8/8/8/PP6/4K3 w - - 0 1").unwrap();
+        assert_eq!(pawns.game_phase(), 0.0);
+    }
+
+    #[test]
+    fn test_king_centralization_in_endgame() {
+        let centralized = Board::from_fen("4k3/pp6/8/8/3K4/8/PP6/8 w - - 0 1").unwrap();
+        let cornered = Board::from_fen("4k3/pp6/8/8/8/8/PP6/K7 w - - 0 1").unwrap();
+        assert!(centralized.evaluate_board() > cornered.evaluate_board());
+    }
+
+    #[test]
+    fn test_bishop_pair_bonus() {
+        // White kept both bishops; Black gave one up for a knight
+        let pair = Board::from_fen("1n2kb2/8/8/8/8/8/8/2B1KB2 w - - 0 1").unwrap();
+        // The same, but White was the one who traded a bishop
+        let no_pair = Board::from_fen("1n2kb2/8/8/8/8/8/8/1N2KB2 w - - 0 1").unwrap();
+
+        assert_eq!(pair.evaluate_bishop_pair(), BISHOP_PAIR_BONUS);
+        assert_eq!(no_pair.evaluate_bishop_pair(), 0);
+        assert!(pair.evaluate_board() > no_pair.evaluate_board());
+
+        let black_pair = Board::from_fen("2b1kb2/8/8/8/8/8/8/1N2KB2 w - - 0 1").unwrap();
+        assert_eq!(black_pair.evaluate_bishop_pair(), -BISHOP_PAIR_BONUS);
+    }
+
+    #[test]
+    fn test_doubled_pawns_are_penalized() {
+        // Three center pawns are worth 5 each, and the e-file is doubled.
+        // With no enemy pawns around they are all passed, too.
+        let expected = 15 - DOUBLED_PAWN_PENALTY + 2 * PASSED_PAWN_BONUS[1] + PASSED_PAWN_BONUS[2];
+        let white = Board::from_fen("4k3/8/8/8/8/4P3/3PP3/4K3 w - - 0 1").unwrap();
+        assert_eq!(white.evaluate_pawn_structure(), expected);
+
+        let black = Board::from_fen("4k3/3pp3/4p3/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(black.evaluate_pawn_structure(), -expected);
+    }
+
+    #[test]
+    fn test_isolated_pawns_are_penalized() {
+        // The a-pawn is worth 2 and has no neighbors; d and e support each
+        // other. All three are passed.
+        let expected = 12 - ISOLATED_PAWN_PENALTY + 3 * PASSED_PAWN_BONUS[1];
+        let white = Board::from_fen("4k3/8/8/8/8/8/P2PP3/4K3 w - - 0 1").unwrap();
+        assert_eq!(white.evaluate_pawn_structure(), expected);
+
+        let black = Board::from_fen("4k3/p2pp3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(black.evaluate_pawn_structure(), -expected);
+    }
+
+    #[test]
+    fn test_passed_pawn_bonus() {
+        // The e6 pawn is passed; the black a-pawn is not in its way
+        let passed = Board::from_fen("4k3/p7/4P3/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let e6 = Position { row: 5, col: 4 };
+        assert!(passed.is_passed_pawn(e6, Color::White));
+        assert!(passed.is_passed_pawn(Position { row: 6, col: 0 }, Color::Black));
+
+        // A black pawn on f7 guards the e-pawn's path
+        let blocked = Board::from_fen("4k3/p4p2/4P3/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(!blocked.is_passed_pawn(e6, Color::White));
+
+        // A pawn behind it doesn't stop it
+        let behind = Board::from_fen("4k3/p7/4P3/5p2/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(behind.is_passed_pawn(e6, Color::White));
+
+        let white_passer = PASSED_PAWN_BONUS[5];
+        let black_passer = PASSED_PAWN_BONUS[1];
+        assert!(white_passer >= 20);
+        // Both pawns are isolated and e6 is a center pawn
+        assert_eq!(
+            passed.evaluate_pawn_structure(),
+            white_passer - black_passer + 5 - 2
+        );
+    }
+
+    #[test]
+    fn test_material_ignores_kings() {
+        let board = Board::new();
+        assert_eq!(board.material_balance(), 0);
+
+        let no_black_queen =
+            Board::from_fen("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        assert_eq!(no_black_queen.material_balance(), Piece::Queen.value());
+
+        let bare_kings = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(bare_kings.material_balance(), 0);
+    }
+
+    #[test]
+    fn test_material_balance_after_losing_a_rook() {
+        let mut board = Board::new();
+        board.squares[7][0] = Square::new(Piece::Empty, None);
+        assert_eq!(board.material_balance(), 50);
+    }
+
+    #[test]
+    fn test_prefers_the_quickest_mate() {
+        // Mate in two with the rook roller, e.g. 1. Ra7 Kg8 2. Rb8#
+        let mut board = Board::from_fen("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1").unwrap();
+        let result = board.search(3);
+        assert_eq!(result.score, MATE - 3);
+        assert_eq!(result.pv.len(), 3);
+
+        for mv in result.pv {
+            board.make_move(mv);
+        }
+        assert!(board.is_checkmate(Color::Black));
+    }
+
+    #[test]
+    fn test_stalemate_scores_as_draw() {
+        let mut stalemate = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(
+            stalemate.minimax(1, -INFINITY, INFINITY, &mut Vec::new()),
+            0
+        );
+
+        // Qf7 would stalemate; White has to find something else
+        let mut board = Board::from_fen("7k/8/6K1/8/8/8/5Q2/8 w - - 0 1").unwrap();
+        let result = board.search(1);
+        assert!(result.score > 0);
+        board.make_move(result.best_move.unwrap());
+        assert!(!board.is_stalemate(Color::Black));
+    }
+
+    #[test]
+    fn test_board_equality_after_undo() {
+        let mut board =
+            Board::from_fen("r3k2r/pppq1ppp/2n2n2/3pp3/4P3/2N2N2/PPPPQPPP/R3K2R w KQkq d6 0 7")
+                .unwrap();
+        board.search(1);
+        let original = board.clone();
+
+        for mv in board.get_legal_moves(board.turn) {
+            board.make_move(mv);
+            assert_ne!(board, original);
+            board.undo_move();
+            assert_eq!(board, original);
+        }
+    }
+
+    #[test]
+    fn test_builder_places_only_the_given_pieces() {
+        let board = Board::builder()
+            .place(Piece::King, Color::White, Position { row: 0, col: 4 })
+            .place(Piece::Queen, Color::White, Position { row: 0, col: 3 })
+            .place(Piece::King, Color::Black, Position { row: 7, col: 4 })
+            .turn(Color::Black)
+            .build();
+
+        let occupied: Vec<Square> = board
+            .squares
+            .iter()
+            .flatten()
+            .filter(|square| square.piece != Piece::Empty)
+            .copied()
+            .collect();
+        assert_eq!(occupied.len(), 3);
+        assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/8/3QK3 b - - 0 1");
+        assert!(!board.is_insufficient_material());
+
+        assert!(Board::empty()
+            .squares
+            .iter()
+            .flatten()
+            .all(|square| square.piece == Piece::Empty));
+    }
+
+    #[test]
+    fn test_pieces_iterates_one_color() {
+        let board = Board::new();
+        for color in [Color::White, Color::Black] {
+            let pieces: Vec<(Position, Piece)> = board.pieces(color).collect();
+            assert_eq!(pieces.len(), 16);
+            assert!(pieces
+                .iter()
+                .all(|(pos, _)| board.squares[pos.row][pos.col].color == Some(color)));
+        }
+        assert_eq!(
+            board.pieces(Color::White).next(),
+            Some((Position { row: 0, col: 0 }, Piece::Rook))
+        );
+    }
+
+    #[test]
+    fn test_cached_king_position_follows_moves() {
+        let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(board.kings[0], Some(Position { row: 0, col: 4 }));
+        assert_eq!(board.kings[1], Some(Position { row: 7, col: 4 }));
+
+        let castle = board.parse_san("O-O").unwrap();
+        board.make_move(castle);
+        assert_eq!(board.kings[0], Some(Position { row: 0, col: 6 }));
+        assert_eq!(board.find_king(Color::White), Some((0, 6)));
+
+        let king_step = board.parse_san("Kd7").unwrap();
+        board.make_move(king_step);
+        assert_eq!(board.kings[1], Some(Position { row: 6, col: 3 }));
+
+        board.undo_move();
+        board.undo_move();
+        assert_eq!(board.kings[0], Some(Position { row: 0, col: 4 }));
+        assert_eq!(board.kings[1], Some(Position { row: 7, col: 4 }));
+    }
+
+    #[test]
+    fn test_find_king_survives_direct_edits() {
+        let mut board = Board::new();
+        board.squares[0][4] = Square::new(Piece::Empty, None);
+        board.squares[2][2] = Square::new(Piece::King, Some(Color::White));
+        assert_eq!(board.find_king(Color::White), Some((2, 2)));
+    }
+
+    #[test]
+    fn test_is_square_attacked() {
+        // Knight on f3 hits e5
+        let knight = Board::from_fen("4k3/8/8/8/8/5N2/8/4K3 w - - 0 1").unwrap();
+        assert!(knight.is_square_attacked(Position { row: 4, col: 4 }, Color::White));
+        assert!(!knight.is_square_attacked(Position { row: 4, col: 5 }, Color::White));
+
+        // The rook on e8 pins the e4 knight, hitting everything up to it
+        let pin = Board::from_fen("4r1k1/8/8/8/4N3/8/8/4K3 w - - 0 1").unwrap();
+        assert!(pin.is_square_attacked(Position { row: 4, col: 4 }, Color::Black));
+        assert!(pin.is_square_attacked(Position { row: 3, col: 4 }, Color::Black));
+        assert!(!pin.is_square_attacked(Position { row: 0, col: 4 }, Color::Black));
+        let mut pinned = pin.clone();
+        // Illegal, so it has to be played as a pseudo-legal move
+        let knight_move = find_move(
+            &pinned,
+            Position { row: 3, col: 4 },
+            Position { row: 5, col: 5 },
+        );
+        pinned.make_move(knight_move);
+        assert!(pinned.is_square_attacked(Position { row: 0, col: 4 }, Color::Black));
+        assert!(pinned.is_in_check(Color::White));
+
+        // A black pawn on d5 hits c4 and e4, but not d4
+        let pawn = Board::from_fen("4k3/8/8/3p4/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(pawn.is_square_attacked(Position { row: 3, col: 2 }, Color::Black));
+        assert!(pawn.is_square_attacked(Position { row: 3, col: 4 }, Color::Black));
+        assert!(!pawn.is_square_attacked(Position { row: 3, col: 3 }, Color::Black));
+        assert!(!pawn.is_square_attacked(Position { row: 5, col: 2 }, Color::Black));
+    }
+
+    #[test]
+    fn test_mobility_term_only_changes_evaluation() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3";
+        let with_mobility = Board::from_fen(fen).unwrap();
+        let mut without_mobility = Board::from_fen(fen).unwrap();
+        without_mobility.mobility_term = false;
+
+        assert_ne!(
+            with_mobility.evaluate_board(),
+            without_mobility.evaluate_board()
+        );
+        assert_eq!(
+            with_mobility.get_legal_moves(Color::Black),
+            without_mobility.get_legal_moves(Color::Black)
+        );
+    }
+
+    #[test]
+    fn test_eval_params_scale_mobility() {
+        // White's rooks have far more room than Black's king and pawns
+        let fen = "4k3/pppppppp/8/8/8/8/8/R3K2R w KQ - 0 1";
+        let normal = Board::from_fen(fen).unwrap();
+        let mut doubled = Board::from_fen(fen).unwrap();
+        doubled.set_eval_params(EvalParams {
+            mobility_weight: 2,
+            ..EvalParams::default()
+        });
+
+        let imbalance = normal.get_valid_moves(Color::White).len() as i32
+            - normal.get_valid_moves(Color::Black).len() as i32;
+        assert!(imbalance > 0);
+        assert_eq!(
+            doubled.evaluate_board() - normal.evaluate_board(),
+            imbalance
+        );
+    }
+
+    #[test]
+    fn test_search_avoids_repeating_when_winning() {
+        let fen = "7k/8/8/8/8/8/6PP/3Q2K1 w - - 0 1";
+        let mut fresh = Board::from_fen(fen).unwrap();
+        let best = fresh.search(3).best_move.unwrap();
+        assert_eq!(fresh.move_to_san(&best), "Qd7");
+
+        // Reach the same position from the one Qd7 would lead to
+        let mut board = Board::from_fen("7k/3Q4/8/8/8/8/6PP/6K1 b - - 1 1").unwrap();
+        board.apply_san_line("Kg8 Qd1 Kh8").unwrap();
+        assert_eq!(board, fresh);
+
+        let result = board.search(3);
+        let mv = result.best_move.unwrap();
+        assert_ne!(board.move_to_san(&mv), "Qd7");
+        assert!(result.score > 0);
+    }
+
+    #[test]
+    fn test_quiescence_keeps_checks_and_drops_quiet_moves() {
+        // The knight blocks the rook's file to the black king
+        let board = Board::from_fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1").unwrap();
+        let at = |square| Position::from_algebraic(square).unwrap();
+        let moves = board.quiescence_moves();
+        let has = |from, to| {
+            moves
+                .iter()
+                .any(|mv| mv.from == at(from) && mv.to == at(to))
+        };
+
+        assert!(has("e4", "c3"), "discovered check");
+        assert!(has("e4", "f6"), "direct check");
+        assert!(!has("g1", "f1"));
+        assert!(!has("e1", "a1"));
+    }
+
+    #[test]
+    fn test_quiescence_stops_at_the_ply_cap() {
+        // Pieces bunched in the center with captures everywhere, one of
+        // them an even trade
+        let fen = "r1bqk2r/ppp2ppp/3p1n2/2b1p3/2BnP3/2NP1N2/PPP2PPP/R1BQK2R w KQkq - 0 6";
+        let mut board = Board::from_fen(fen).unwrap();
+        assert!(!board.quiescence_moves().is_empty());
+
+        let score = board.quiescence_search(-INFINITY, INFINITY, MAX_QUIESCENCE_PLY);
+        assert_eq!(score, board.evaluate_relative());
+        assert_eq!(board.nodes, 1);
+
+        board.nodes = 0;
+        board.quiescence_search(-INFINITY, INFINITY, 0);
+        assert!(board.nodes > 1);
+        assert_eq!(board, Board::from_fen(fen).unwrap());
+    }
+
+    #[test]
+    fn test_null_move_pruning() {
+        let fen = "6k1/pp3ppp/8/3r4/8/5N2/PP3PPP/3R2K1 w - - 0 25";
+        let mut plain = Board::from_fen(fen).unwrap();
+        // Mobility only slows the evaluation down here
+        plain.mobility_term = false;
+        plain.null_move = false;
+        plain.search(3);
+        let mut pruned = Board::from_fen(fen).unwrap();
+        pruned.mobility_term = false;
+        pruned.search(3);
+        assert!(
+            pruned.nodes < plain.nodes,
+            "{} >= {}",
+            pruned.nodes,
+            plain.nodes
+        );
+
+        // Still finds the knight fork that wins the rook
+        let mut fork = Board::from_fen("r3k3/8/8/1N6/8/8/8/4K3 w - - 0 1").unwrap();
+        let result = fork.search(2);
+        assert_eq!(fork.move_to_san(&result.best_move.unwrap()), "Nc7+");
+        assert_eq!(fork.null_plies, 0);
+    }
+
+    #[test]
+    fn test_late_move_reductions() {
+        let fen = "6k1/pp3ppp/8/3r4/8/5N2/PP3PPP/3R2K1 w - - 0 25";
+        let mut plain = Board::from_fen(fen).unwrap();
+        plain.mobility_term = false;
+        plain.late_move_reductions = false;
+        plain.search(4);
+        let mut reduced = Board::from_fen(fen).unwrap();
+        reduced.mobility_term = false;
+        reduced.search(4);
+        assert!(
+            reduced.nodes < plain.nodes,
+            "{} >= {}",
+            reduced.nodes,
+            plain.nodes
+        );
+
+        let mut fork = Board::from_fen("r3k3/8/8/1N6/8/8/8/4K3 w - - 0 1").unwrap();
+        let result = fork.search(3);
+        assert_eq!(fork.move_to_san(&result.best_move.unwrap()), "Nc7+");
+    }
+
+    #[test]
+    fn test_gives_check_and_checkmate() {
+        let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let at = |square| Position::from_algebraic(square).unwrap();
+        let mate = find_move(&board, at("a1"), at("a8"));
+        let quiet = find_move(&board, at("a1"), at("a5"));
+        assert!(board.gives_check(&mate));
+        assert!(board.gives_checkmate(&mate));
+        assert!(!board.gives_check(&quiet));
+        assert!(!board.gives_checkmate(&quiet));
+        assert_eq!(
+            board,
+            Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap()
+        );
+
+        // Checks that can be escaped
+        let board = Board::from_fen("6k1/8/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let check = find_move(&board, at("a1"), at("a8"));
+        assert!(board.gives_check(&check));
+        assert!(!board.clone().gives_checkmate(&check));
+    }
+
+    #[test]
+    fn test_gives_check_matches_playing_the_move() {
+        for fen in [
+            // Castling with check from the rook
+            "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+            // En passant uncovering the rook
+            "8/8/8/k1pP3R/8/8/8/4K3 w - c6 0 1",
+            // Promotions, some with check
+            "3k4/P7/8/8/8/8/8/4K3 w - - 0 1",
+            "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+        ] {
+            let board = Board::from_fen(fen).unwrap();
+            for mv in board.get_legal_moves(board.turn) {
+                let mut played = board.clone();
+                played.make_move(mv);
+                assert_eq!(
+                    board.gives_check(&mv),
+                    played.is_in_check(played.turn),
+                    "{} {:?}",
+                    fen,
+                    mv
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate_relative() {
+        let mut board = Board::new();
+        assert_eq!(board.evaluate_relative(), 0);
+        board.turn = Color::Black;
+        assert_eq!(board.evaluate_relative(), 0);
+
+        // White is a knight up
+        let mut board =
+            Board::from_fen("rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let white_view = board.evaluate_relative();
+        assert!(white_view > 0);
+        board.turn = Color::Black;
+        assert_eq!(board.evaluate_relative(), -white_view);
+    }
+
+    #[test]
+    fn test_to_string_with_coords() {
+        let text = Board::new().to_string_with_coords();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert_eq!(lines[0], "8 ♜ ♞ ♝ ♛ ♚ ♝ ♞ ♜ ");
+        assert_eq!(lines[7], "1 ♖ ♘ ♗ ♕ ♔ ♗ ♘ ♖ ");
+        assert_eq!(lines[8], "  a b c d e f g h");
+    }
+
+    #[test]
+    fn test_display_puts_white_at_the_bottom() {
+        let board = Board::new();
+        let text = board.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "♜ ♞ ♝ ♛ ♚ ♝ ♞ ♜ ");
+        assert_eq!(lines[7], "♖ ♘ ♗ ♕ ♔ ♗ ♘ ♖ ");
+
+        let flipped = format!("{board:#}");
+        let lines: Vec<&str> = flipped.lines().collect();
+        assert_eq!(lines[0], "♖ ♘ ♗ ♔ ♕ ♗ ♘ ♖ ");
+        assert_eq!(lines[7], "♜ ♞ ♝ ♚ ♛ ♝ ♞ ♜ ");
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        let expected = "\
+rnbqkbnr
+pppppppp
+........
+........
+........
+........
+PPPPPPPP
+RNBQKBNR
+";
+        assert_eq!(Board::new().to_ascii(), expected);
+    }
+
+    #[test]
+    fn test_king_safety_rewards_a_pawn_shield() {
+        let intact = Board::from_fen("r4rk1/ppp2ppp/8/8/8/8/PPP2PPP/R4RK1 w - - 0 1").unwrap();
+        let pushed = Board::from_fen("r4rk1/ppp2ppp/8/8/5PPP/8/PPP5/R4RK1 w - - 0 1").unwrap();
+        let missing = Board::from_fen("r4rk1/ppp2ppp/8/8/8/8/PPP5/R4RK1 w - - 0 1").unwrap();
+
+        assert_eq!(intact.evaluate_king_safety(), 0);
+        assert!(intact.evaluate_king_safety() > pushed.evaluate_king_safety());
+        assert!(pushed.evaluate_king_safety() > missing.evaluate_king_safety());
+    }
+
+    #[test]
+    fn test_rooks_prefer_open_files() {
+        let blocked = Board::from_fen("4k3/4p3/8/8/8/8/4P3/4RK2 w - - 0 1").unwrap();
+        let semi_open = Board::from_fen("4k3/4p3/8/8/8/8/3P4/4RK2 w - - 0 1").unwrap();
+        let open = Board::from_fen("4k3/3p4/8/8/8/8/3P4/4RK2 w - - 0 1").unwrap();
+
+        assert_eq!(blocked.evaluate_rook_files(), 0);
+        assert_eq!(semi_open.evaluate_rook_files(), ROOK_SEMI_OPEN_FILE_BONUS);
+        assert_eq!(open.evaluate_rook_files(), ROOK_OPEN_FILE_BONUS);
+    }
+
+    #[test]
+    fn test_on_board() {
+        for (i, j) in [(0, 0), (0, 7), (7, 0), (7, 7)] {
+            assert!(on_board(i, j), "({}, {})", i, j);
+        }
+        for (i, j) in [
+            (-1, 0),
+            (0, -1),
+            (-1, 7),
+            (0, 8),
+            (8, 0),
+            (7, -1),
+            (8, 7),
+            (7, 8),
+        ] {
+            assert!(!on_board(i, j), "({}, {})", i, j);
+        }
+    }
+
+    #[test]
+    fn test_check_evasions() {
+        let uci_moves = |board: &Board| {
+            let mut moves: Vec<String> = board
+                .get_legal_moves(board.turn)
+                .iter()
+                .map(|mv| board.move_to_uci(mv))
+                .collect();
+            moves.sort();
+            moves
+        };
+
+        // The a1 rook checks: take it, block on d1, or step off the rank
+        let board = Board::from_fen("4k3/8/8/8/3B4/4N3/8/r3K2R w K - 0 1").unwrap();
+        assert_eq!(uci_moves(&board), ["d4a1", "e1d2", "e1e2", "e1f2", "e3d1"]);
+
+        // Rook and knight both check, so only the king can move
+        let board = Board::from_fen("4k3/8/8/8/3B4/5n2/8/r3K3 w - - 0 1").unwrap();
+        assert_eq!(uci_moves(&board), ["e1e2", "e1f2"]);
+    }
+
+    #[test]
+    fn test_pinned_pieces() {
+        // The b5 bishop pins the c6 knight. The e5 pawn is not pinned by
+        // the rook, since White's own pawn stands in between
+        let board =
+            Board::from_fen("r1bqk2r/ppp2ppp/2n2n2/1B2p3/4P3/5N2/PPPP1PPP/RNBQR1K1 b kq - 0 1")
+                .unwrap();
+        let at = |square| Position::from_algebraic(square).unwrap();
+        assert_eq!(
+            board.pinned_pieces(Color::Black),
+            vec![(at("c6"), at("b5"))]
+        );
+        assert!(board.pinned_pieces(Color::White).is_empty());
+
+        let knight_moves = board.legal_moves_from(at("c6"));
+        assert!(knight_moves.is_empty());
+    }
+
+    #[test]
+    fn test_attackers_of() {
+        // e5 is covered by the d4 pawn and the f3 knight, but not the
+        // bishop behind the pawn
+        let board = Board::from_fen("4k3/8/8/4p3/3P4/2B2N2/8/4K3 w - - 0 1").unwrap();
+        let at = |square| Position::from_algebraic(square).unwrap();
+        let mut attackers = board.attackers_of(at("e5"), Color::White);
+        attackers.sort_by_key(|pos| pos.col);
+        assert_eq!(attackers, vec![at("d4"), at("f3")]);
+        assert!(board.is_square_attacked(at("e5"), Color::White));
+
+        assert!(board.attackers_of(at("e4"), Color::White).is_empty());
+        assert!(!board.is_square_attacked(at("e4"), Color::White));
+    }
+
+    #[test]
+    fn test_pawn_on_last_rank_has_no_forward_move() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/p3K3 b - - 0 1").unwrap();
+        let moves = board.get_valid_moves(Color::Black);
+        assert!(moves.iter().all(|mv| mv.piece != Piece::Pawn));
+
+        let board = Board::from_fen("P3k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let moves = board.get_valid_moves(Color::White);
+        assert!(moves.iter().all(|mv| mv.piece != Piece::Pawn));
+    }
+
+    #[test]
+    fn test_legal_move_count() {
+        let board = Board::new();
+        assert_eq!(board.legal_move_count(Color::White), 20);
+        assert_eq!(board.legal_move_count(Color::Black), 20);
+
+        let mated = Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert_eq!(mated.legal_move_count(Color::Black), 0);
+        assert!(mated.is_checkmate(Color::Black));
+        assert!(!mated.is_stalemate(Color::Black));
+    }
+
+    #[test]
+    fn test_insufficient_material_evaluates_as_draw() {
+        let mut board = Board::from_fen("8/8/8/3k4/8/8/8/2B1K3 w - - 0 1").unwrap();
+        assert_ne!(board.evaluate_board(), 0);
+        assert_eq!(board.evaluate_relative(), 0);
+        board.turn = Color::Black;
+        assert_eq!(board.evaluate_relative(), 0);
+
+        let winning = Board::from_fen("8/8/8/3k4/8/8/8/2R1K3 w - - 0 1").unwrap();
+        assert!(winning.evaluate_relative() > 0);
+    }
+
+    #[test]
+    fn test_legal_moves_from() {
+        let board = Board::new();
+        let at = |square| Position::from_algebraic(square).unwrap();
+
+        assert!(board.legal_moves_from(at("e1")).is_empty());
+        assert!(board.legal_moves_from(at("e4")).is_empty());
+        assert!(board.legal_moves_from(at("b8")).is_empty());
+
+        let mut targets: Vec<Position> = board
+            .legal_moves_from(at("b1"))
+            .iter()
+            .map(|mv| mv.to)
+            .collect();
+        targets.sort_by_key(|pos| pos.col);
+        assert_eq!(targets, vec![at("a3"), at("c3")]);
+    }
+
+    #[test]
+    fn test_seeded_boards_play_the_same_moves() {
+        let mut first = Board::new();
+        let mut second = Board::new();
+        first.set_seed(42);
+        second.set_seed(42);
+        for _ in 0..6 {
+            let mv = first.search(2).best_move.unwrap();
+            assert_eq!(second.search(2).best_move, Some(mv));
+            first.make_move(mv);
+            second.make_move(mv);
+        }
+    }
+
+    #[test]
+    fn test_nodes_searched_grows_with_depth() {
+        let mut board = Board::new();
+        assert_eq!(board.nodes_searched(), 0);
+        board.search(2);
+        let shallow = board.nodes_searched();
+        assert!(shallow > 20);
+        board.search(3);
+        assert!(board.nodes_searched() > shallow);
+    }
+
+    #[test]
+    fn test_search_reports_each_depth() {
+        let mut board = Board::new();
+        let mut infos = Vec::new();
+        let result = board.search_with_callback(3, |info| infos.push(info));
+
+        let depths: Vec<usize> = infos.iter().map(|info| info.depth).collect();
+        assert_eq!(depths, vec![1, 2, 3]);
+        assert!(infos.windows(2).all(|pair| pair[0].nodes <= pair[1].nodes));
+        let last = infos.last().unwrap();
+        assert_eq!(last.score, result.score);
+        assert_eq!(last.pv, result.pv);
+    }
+
+    #[test]
+    fn test_aspiration_windows() {
+        let fen = "6k1/pp3ppp/8/3r4/8/5N2/PP3PPP/3R2K1 w - - 0 25";
+        let mut full = Board::from_fen(fen).unwrap();
+        full.mobility_term = false;
+        full.aspiration_windows = false;
+        let full_result = full.search(4);
+        let mut narrow = Board::from_fen(fen).unwrap();
+        narrow.mobility_term = false;
+        let narrow_result = narrow.search(4);
+        assert_eq!(narrow_result.best_move, full_result.best_move);
+        assert!(
+            narrow.nodes < full.nodes,
+            "{} >= {}",
+            narrow.nodes,
+            full.nodes
+        );
+
+        // The mate only shows up at the last iteration, far outside the
+        // window around the previous score
+        let mut mate = Board::from_fen("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1").unwrap();
+        let shallow = mate.search(2).score;
+        let result = mate.search(3);
+        assert!(shallow < MATE_THRESHOLD);
+        assert_eq!(result.score, MATE - 3);
+        for mv in result.pv {
+            mate.make_move(mv);
+        }
+        assert!(mate.is_checkmate(Color::Black));
+    }
 }
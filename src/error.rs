@@ -0,0 +1,110 @@
+use std::{fmt, str::FromStr};
+
+use crate::{Board, FenError, MoveError, PgnError, Position, SanError};
+
+/// Any failure to turn text into a position or a move. Every parser in the
+/// crate returns it, with the FEN, SAN and PGN specifics as the payloads of
+/// their variants, so callers can handle failures in one place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    BadFen(FenError),
+    /// Text that isn't usable SAN, including ambiguous moves.
+    BadSan(SanError),
+    /// A well-formed move that can't be played in the position.
+    IllegalMove(String),
+    /// A square name that isn't on the board, such as `i9`.
+    OutOfBounds(String),
+    /// Text that isn't a long algebraic move such as `e2e4` or `e7e8q`.
+    BadUci(String),
+    BadPgn(PgnError),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::BadFen(err) => write!(f, "bad FEN: {}", err),
+            ParseError::BadSan(err) => write!(f, "bad SAN: {}", err),
+            ParseError::IllegalMove(mv) => write!(f, "illegal move: {}", mv),
+            ParseError::OutOfBounds(square) => write!(f, "'{}' is not a square", square),
+            ParseError::BadUci(mv) => write!(f, "'{}' is not a long algebraic move", mv),
+            ParseError::BadPgn(err) => write!(f, "bad PGN: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<FenError> for ParseError {
+    fn from(err: FenError) -> Self {
+        ParseError::BadFen(err)
+    }
+}
+
+impl From<SanError> for ParseError {
+    fn from(err: SanError) -> Self {
+        ParseError::BadSan(err)
+    }
+}
+
+impl From<MoveError> for ParseError {
+    fn from(err: MoveError) -> Self {
+        ParseError::IllegalMove(err.to_string())
+    }
+}
+
+impl From<PgnError> for ParseError {
+    fn from(err: PgnError) -> Self {
+        ParseError::BadPgn(err)
+    }
+}
+
+/// Parses a FEN string.
+impl FromStr for Board {
+    type Err = ParseError;
+
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        Board::from_fen(fen)
+    }
+}
+
+/// Parses a square name such as `e4`.
+impl FromStr for Position {
+    type Err = ParseError;
+
+    fn from_str(square: &str) -> Result<Self, Self::Err> {
+        Position::from_algebraic(square).ok_or_else(|| ParseError::OutOfBounds(square.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, FenError, ParseError, Position, SanError};
+
+    #[test]
+    fn test_nine_ranks_is_a_bad_fen() {
+        let err = "rnbqkbnr/pppppppp/8/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+            .parse::<Board>()
+            .unwrap_err();
+        assert_eq!(err, ParseError::BadFen(FenError::WrongRankCount(9)));
+        assert_eq!(err.to_string(), "bad FEN: expected 8 ranks, found 9");
+    }
+
+    #[test]
+    fn test_parse_errors_convert() {
+        assert_eq!(
+            "i9".parse::<Position>(),
+            Err(ParseError::OutOfBounds("i9".to_string()))
+        );
+        assert_eq!("e4".parse::<Position>(), Ok(Position { row: 3, col: 4 }));
+
+        let board = Board::new();
+        assert_eq!(
+            board.parse_san("e5"),
+            Err(ParseError::IllegalMove("e5".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("zz"),
+            Err(ParseError::BadSan(SanError::Invalid("zz".to_string())))
+        );
+    }
+}
@@ -0,0 +1,473 @@
+use std::fmt;
+
+use crate::{piece::Piece, Board, CastlingRights, Color, ParseError, Position, Square};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    /// A FEN needs 6 space-separated fields (the two counters may be omitted).
+    WrongFieldCount(usize),
+    /// The placement field must describe exactly 8 ranks.
+    WrongRankCount(usize),
+    /// A rank that doesn't add up to 8 squares.
+    BadRank(String),
+    InvalidPiece(char),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::WrongFieldCount(count) => {
+                write!(f, "expected 4 or 6 fields, found {}", count)
+            }
+            FenError::WrongRankCount(count) => write!(f, "expected 8 ranks, found {}", count),
+            FenError::BadRank(rank) => write!(f, "rank '{}' does not describe 8 squares", rank),
+            FenError::InvalidPiece(c) => write!(f, "invalid piece character '{}'", c),
+            FenError::InvalidSideToMove(side) => write!(f, "invalid side to move '{}'", side),
+            FenError::InvalidCastling(castling) => {
+                write!(f, "invalid castling availability '{}'", castling)
+            }
+            FenError::InvalidEnPassant(square) => {
+                write!(f, "invalid en passant square '{}'", square)
+            }
+            FenError::InvalidHalfmoveClock(clock) => {
+                write!(f, "invalid halfmove clock '{}'", clock)
+            }
+            FenError::InvalidFullmoveNumber(number) => {
+                write!(f, "invalid fullmove number '{}'", number)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl Board {
+    /// Sets up a position from Forsyth-Edwards Notation, e.g.
+    /// `rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1`. Problems
+    /// with the text come back as `ParseError::BadFen`.
+    pub fn from_fen(fen: &str) -> Result<Board, ParseError> {
+        Ok(Board::parse_fen(fen)?)
+    }
+
+    fn parse_fen(fen: &str) -> Result<Board, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 6 && fields.len() != 4 {
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+
+        let mut board = Board::new();
+        board.squares = parse_placement(fields[0])?;
+        board.turn = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            side => return Err(FenError::InvalidSideToMove(side.to_string())),
+        };
+        let (castling, chess960_files) = parse_castling(fields[2], &board.squares)?;
+        board.castling = castling;
+        if let Some((king_col, rook_cols)) = chess960_files {
+            board.chess960 = true;
+            board.castling_king_col = king_col;
+            board.castling_rook_cols = rook_cols;
+        }
+        board.en_passant = match fields[3] {
+            "-" => None,
+            square => Some(
+                Position::from_algebraic(square)
+                    .filter(|pos| pos.row == 2 || pos.row == 5)
+                    .ok_or_else(|| FenError::InvalidEnPassant(square.to_string()))?,
+            ),
+        };
+
+        if fields.len() == 6 {
+            board.halfmove_clock = fields[4]
+                .parse()
+                .map_err(|_| FenError::InvalidHalfmoveClock(fields[4].to_string()))?;
+            board.fullmove_number = fields[5]
+                .parse()
+                .ok()
+                .filter(|&number| number > 0)
+                .ok_or_else(|| FenError::InvalidFullmoveNumber(fields[5].to_string()))?;
+        } else {
+            board.halfmove_clock = 0;
+            board.fullmove_number = 1;
+        }
+
+        board.locate_kings();
+        board.refresh_hash();
+        Ok(board)
+    }
+
+    /// Serializes the position to the standard six-field FEN. Chess960
+    /// castling rights are written as the files of the rooks, Shredder-FEN
+    /// style, e.g. `HFhf`.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for (rank_index, row) in self.squares.iter().rev().enumerate() {
+            if rank_index > 0 {
+                fen.push('/');
+            }
+            let mut empty = 0;
+            for square in row.iter() {
+                match piece_char(square) {
+                    Some(c) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(c);
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+        }
+
+        fen.push(' ');
+        fen.push(match self.turn {
+            Color::White => 'w',
+            Color::Black => 'b',
+        });
+
+        fen.push(' ');
+        let [queenside_file, kingside_file] = self
+            .castling_rook_cols
+            .map(|col| (b'A' + col as u8) as char);
+        let (kingside, queenside) = if self.chess960 {
+            (kingside_file, queenside_file)
+        } else {
+            ('K', 'Q')
+        };
+        let rights = [
+            (self.castling.white_kingside, kingside),
+            (self.castling.white_queenside, queenside),
+            (self.castling.black_kingside, kingside.to_ascii_lowercase()),
+            (
+                self.castling.black_queenside,
+                queenside.to_ascii_lowercase(),
+            ),
+        ];
+        let castling: String = rights
+            .iter()
+            .filter(|(allowed, _)| *allowed)
+            .map(|(_, c)| *c)
+            .collect();
+        fen.push_str(if castling.is_empty() { "-" } else { &castling });
+
+        fen.push(' ');
+        match self.en_passant {
+            Some(pos) => fen.push_str(&pos.to_algebraic()),
+            None => fen.push('-'),
+        }
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+        fen
+    }
+}
+
+fn parse_placement(placement: &str) -> Result<[[Square; 8]; 8], FenError> {
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::WrongRankCount(ranks.len()));
+    }
+
+    let mut squares = [[Square::new(Piece::Empty, None); 8]; 8];
+    // FEN lists rank 8 first, which is row 7 on our board
+    for (rank_index, rank) in ranks.iter().enumerate() {
+        let row = 7 - rank_index;
+        let mut col = 0;
+        for c in rank.chars() {
+            if let Some(skip) = c.to_digit(10) {
+                if skip == 0 || skip > 8 {
+                    return Err(FenError::BadRank(rank.to_string()));
+                }
+                col += skip as usize;
+            } else {
+                if col >= 8 {
+                    return Err(FenError::BadRank(rank.to_string()));
+                }
+                squares[row][col] = parse_piece(c)?;
+                col += 1;
+            }
+            if col > 8 {
+                return Err(FenError::BadRank(rank.to_string()));
+            }
+        }
+        if col != 8 {
+            return Err(FenError::BadRank(rank.to_string()));
+        }
+    }
+
+    Ok(squares)
+}
+
+fn parse_piece(c: char) -> Result<Square, FenError> {
+    let piece = match c.to_ascii_lowercase() {
+        'k' => Piece::King,
+        'q' => Piece::Queen,
+        'r' => Piece::Rook,
+        'b' => Piece::Bishop,
+        'n' => Piece::Knight,
+        'p' => Piece::Pawn,
+        _ => return Err(FenError::InvalidPiece(c)),
+    };
+    let color = if c.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    Ok(Square::new(piece, Some(color)))
+}
+
+pub(crate) fn piece_char(square: &Square) -> Option<char> {
+    let c = match square.piece {
+        Piece::King => 'k',
+        Piece::Queen => 'q',
+        Piece::Rook => 'r',
+        Piece::Bishop => 'b',
+        Piece::Knight => 'n',
+        Piece::Pawn => 'p',
+        Piece::Empty => return None,
+    };
+    match square.color? {
+        Color::White => Some(c.to_ascii_uppercase()),
+        Color::Black => Some(c),
+    }
+}
+
+/// Chess960 castling: the king's file and the queenside and kingside rooks'
+/// files, which are the same for both colors.
+type Chess960Files = (usize, [usize; 2]);
+
+/// Reads `KQkq` style rights, or the rook files of Shredder-FEN such as
+/// `HFhf`, which mark the position as Chess960 and are returned with the
+/// king's file.
+fn parse_castling(
+    field: &str,
+    squares: &[[Square; 8]; 8],
+) -> Result<(CastlingRights, Option<Chess960Files>), FenError> {
+    let invalid = || FenError::InvalidCastling(field.to_string());
+    let mut castling = CastlingRights::none();
+    if field == "-" {
+        return Ok((castling, None));
+    }
+    let shredder = field
+        .chars()
+        .any(|c| matches!(c.to_ascii_lowercase(), 'a'..='h'));
+    let mut king_col = None;
+    let mut rook_cols = [0, 7];
+    for c in field.chars() {
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let kingside = match c.to_ascii_lowercase() {
+            'k' if !shredder => true,
+            'q' if !shredder => false,
+            file @ 'a'..='h' => {
+                let row = match color {
+                    Color::White => 0,
+                    Color::Black => 7,
+                };
+                let king = (0..8)
+                    .find(|&col| squares[row][col] == Square::new(Piece::King, Some(color)))
+                    .ok_or_else(invalid)?;
+                let rook = file as usize - 'a' as usize;
+                if squares[row][rook] != Square::new(Piece::Rook, Some(color))
+                    || king_col.is_some_and(|col| col != king)
+                {
+                    return Err(invalid());
+                }
+                king_col = Some(king);
+                let kingside = rook > king;
+                rook_cols[kingside as usize] = rook;
+                kingside
+            }
+            _ => return Err(invalid()),
+        };
+        let right = match (color, kingside) {
+            (Color::White, true) => &mut castling.white_kingside,
+            (Color::White, false) => &mut castling.white_queenside,
+            (Color::Black, true) => &mut castling.black_kingside,
+            (Color::Black, false) => &mut castling.black_queenside,
+        };
+        if *right {
+            return Err(invalid());
+        }
+        *right = true;
+    }
+    Ok((castling, king_col.map(|king_col| (king_col, rook_cols))))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Board, CastlingRights, Color, FenError, Move, ParseError, Piece, Position, Square,
+    };
+
+    const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn test_from_fen_start_position() {
+        let board = Board::from_fen(START_FEN).unwrap();
+        let initial = Board::new();
+        assert_eq!(board.squares, initial.squares);
+        assert_eq!(board.turn, Color::White);
+        assert_eq!(board.castling, CastlingRights::all());
+        assert_eq!(board.en_passant, None);
+        assert_eq!(board.halfmove_clock, 0);
+        assert_eq!(board.fullmove_number, 1);
+    }
+
+    #[test]
+    fn test_from_fen_en_passant_and_partial_castling() {
+        let board =
+            Board::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3").unwrap();
+        assert_eq!(board.turn, Color::White);
+        assert_eq!(board.en_passant, Some(Position { row: 5, col: 5 }));
+        assert_eq!(
+            board.castling,
+            CastlingRights {
+                white_kingside: true,
+                white_queenside: false,
+                black_kingside: false,
+                black_queenside: true,
+            }
+        );
+        assert_eq!(board.fullmove_number, 3);
+        assert_eq!(
+            board.squares[4][4],
+            Square::new(Piece::Pawn, Some(Color::White))
+        );
+        assert_eq!(
+            board.squares[4][5],
+            Square::new(Piece::Pawn, Some(Color::Black))
+        );
+        assert_eq!(board.squares[1][4], Square::new(Piece::Empty, None));
+    }
+
+    #[test]
+    fn test_from_fen_counters_and_black_to_move() {
+        let board = Board::from_fen("8/8/4k3/8/8/4K3/4P3/8 b - - 12 47").unwrap();
+        assert_eq!(board.turn, Color::Black);
+        assert_eq!(board.castling, CastlingRights::none());
+        assert_eq!(board.halfmove_clock, 12);
+        assert_eq!(board.fullmove_number, 47);
+        assert_eq!(
+            board.squares[5][4],
+            Square::new(Piece::King, Some(Color::Black))
+        );
+    }
+
+    #[test]
+    fn test_from_fen_rejects_malformed_input() {
+        assert_eq!(
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1").err(),
+            Some(ParseError::BadFen(FenError::WrongRankCount(7)))
+        );
+        assert_eq!(
+            Board::from_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").err(),
+            Some(ParseError::BadFen(FenError::BadRank("9".to_string())))
+        );
+        assert_eq!(
+            Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1").err(),
+            Some(ParseError::BadFen(FenError::InvalidPiece('X')))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/8 x - - 0 1").err(),
+            Some(ParseError::BadFen(FenError::InvalidSideToMove(
+                "x".to_string()
+            )))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/8 w KX - 0 1").err(),
+            Some(ParseError::BadFen(FenError::InvalidCastling(
+                "KX".to_string()
+            )))
+        );
+        // A Shredder-FEN file with no rook on it
+        assert_eq!(
+            Board::from_fen("4k3/8/8/8/8/8/8/4K3 w G - 0 1").err(),
+            Some(ParseError::BadFen(FenError::InvalidCastling(
+                "G".to_string()
+            )))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/8 w - e4 0 1").err(),
+            Some(ParseError::BadFen(FenError::InvalidEnPassant(
+                "e4".to_string()
+            )))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/8 w - - x 1").err(),
+            Some(ParseError::BadFen(FenError::InvalidHalfmoveClock(
+                "x".to_string()
+            )))
+        );
+        assert_eq!(
+            Board::from_fen("8/8/8/8/8/8/8/8 w -").err(),
+            Some(ParseError::BadFen(FenError::WrongFieldCount(3)))
+        );
+    }
+
+    #[test]
+    fn test_to_fen_start_position() {
+        assert_eq!(Board::new().to_fen(), START_FEN);
+    }
+
+    #[test]
+    fn test_fen_string_round_trip() {
+        for fen in [
+            START_FEN,
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/8/4k3/8/8/4K3/4P3/8 b - - 12 47",
+        ] {
+            assert_eq!(Board::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn test_board_round_trip_after_moves() {
+        let mut board = Board::new();
+        // e4, then c5
+        for (from, to) in [((1, 4), (3, 4)), ((6, 2), (4, 2))] {
+            board.make_move(Move {
+                from: Position {
+                    row: from.0,
+                    col: from.1,
+                },
+                to: Position {
+                    row: to.0,
+                    col: to.1,
+                },
+                piece: Piece::Pawn,
+                captured: None,
+                promotion: None,
+                score: 0,
+            });
+        }
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2"
+        );
+
+        let parsed = Board::from_fen(&board.to_fen()).unwrap();
+        assert_eq!(parsed.squares, board.squares);
+        assert_eq!(parsed.turn, board.turn);
+        assert_eq!(parsed.castling, board.castling);
+        assert_eq!(parsed.en_passant, board.en_passant);
+        assert_eq!(parsed.halfmove_clock, board.halfmove_clock);
+        assert_eq!(parsed.fullmove_number, board.fullmove_number);
+    }
+}
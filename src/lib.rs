@@ -1,16 +1,47 @@
-pub use board::Board;
+use std::fmt;
+
+pub use board::{
+    Board, BoardBuilder, DrawReason, EvalParams, GameResult, MoveError, SearchInfo, SearchResult,
+};
+pub use error::ParseError;
+pub use fen::FenError;
+pub use opening::Book;
+pub use pgn::{parse_pgn, Game, MoveAnalysis, PgnError};
 pub use piece::Piece;
+pub use san::SanError;
 
 pub mod board;
+pub mod error;
+pub mod fen;
+pub mod opening;
+pub mod pgn;
 pub mod piece;
+pub mod san;
+mod tt;
+pub mod uci;
+mod zobrist;
+
+/// The engine's move for the position in `fen` after searching `depth`
+/// plies, in long algebraic notation such as `e2e4`, or `0000` when the side
+/// to move has no legal move. Takes and returns plain strings and touches
+/// nothing outside the library, so it is easy to expose from WebAssembly.
+pub fn best_move_for_fen(fen: &str, depth: usize) -> Result<String, ParseError> {
+    let mut board = Board::from_fen(fen)?;
+    Ok(board
+        .search(depth)
+        .best_move
+        .map_or_else(|| "0000".to_string(), |mv| board.move_to_uci(&mv)))
+}
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     White,
     Black,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Square {
     pub piece: Piece,
     pub color: Option<Color>, // Use Option to represent empty squares
@@ -22,17 +53,206 @@ impl Square {
     }
 }
 
+impl fmt::Display for Square {
+    /// The piece's figurine, or `.` for an empty square.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let glyph = match (self.piece, self.color) {
+            (_, None) | (Piece::Empty, _) => ".",
+            (Piece::King, Some(Color::White)) => "♔",
+            (Piece::Queen, Some(Color::White)) => "♕",
+            (Piece::Rook, Some(Color::White)) => "♖",
+            (Piece::Bishop, Some(Color::White)) => "♗",
+            (Piece::Knight, Some(Color::White)) => "♘",
+            (Piece::Pawn, Some(Color::White)) => "♙",
+            (Piece::King, Some(Color::Black)) => "♚",
+            (Piece::Queen, Some(Color::Black)) => "♛",
+            (Piece::Rook, Some(Color::Black)) => "♜",
+            (Piece::Bishop, Some(Color::Black)) => "♝",
+            (Piece::Knight, Some(Color::Black)) => "♞",
+            (Piece::Pawn, Some(Color::Black)) => "♟︎",
+        };
+        f.write_str(glyph)
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+impl CastlingRights {
+    pub fn all() -> Self {
+        CastlingRights {
+            white_kingside: true,
+            white_queenside: true,
+            black_kingside: true,
+            black_queenside: true,
+        }
+    }
+
+    pub fn none() -> Self {
+        CastlingRights {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+        }
+    }
+}
+
+/// A square on the board. Row 0 is rank 1 (White's back rank) and column 0
+/// is the a-file, so `e4` is `Position { row: 3, col: 4 }`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     pub row: usize,
     pub col: usize,
 }
 
+impl Position {
+    /// The square at `row` and `col`, or `None` if that is off the board.
+    pub fn new(row: usize, col: usize) -> Option<Position> {
+        let pos = Position { row, col };
+        pos.is_valid().then_some(pos)
+    }
+
+    /// Whether the square is on the board.
+    pub fn is_valid(&self) -> bool {
+        self.row < 8 && self.col < 8
+    }
+
+    /// Parses a square name such as `e4`.
+    pub fn from_algebraic(square: &str) -> Option<Position> {
+        let bytes = square.as_bytes();
+        if bytes.len() != 2 {
+            return None;
+        }
+        let col = bytes[0].wrapping_sub(b'a') as usize;
+        let row = bytes[1].wrapping_sub(b'1') as usize;
+        Position::new(row, col)
+    }
+
+    /// The square's name, such as `e4`.
+    pub fn to_algebraic(&self) -> String {
+        format!("{}{}", (b'a' + self.col as u8) as char, self.row + 1)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Move {
     pub from: Position,
     pub to: Position,
     pub piece: Piece,
     pub captured: Option<Piece>,
+    pub promotion: Option<Piece>,
     pub score: i32,
 }
+
+impl Move {
+    pub fn is_capture(&self) -> bool {
+        self.captured.is_some()
+    }
+
+    pub fn is_promotion(&self) -> bool {
+        self.promotion.is_some()
+    }
+
+    /// Neither a capture nor a promotion.
+    pub fn is_quiet(&self) -> bool {
+        !self.is_capture() && !self.is_promotion()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{best_move_for_fen, Color, FenError, Move, ParseError, Piece, Position, Square};
+
+    #[test]
+    fn test_move_classifiers() {
+        let push = Move {
+            from: Position { row: 1, col: 4 },
+            to: Position { row: 3, col: 4 },
+            piece: Piece::Pawn,
+            captured: None,
+            promotion: None,
+            score: 0,
+        };
+        assert!(push.is_quiet());
+        assert!(!push.is_capture());
+        assert!(!push.is_promotion());
+
+        let capture = Move {
+            captured: Some(Piece::Knight),
+            ..push
+        };
+        assert!(capture.is_capture());
+        assert!(!capture.is_quiet());
+        assert!(!capture.is_promotion());
+
+        let promotion = Move {
+            promotion: Some(Piece::Queen),
+            ..push
+        };
+        assert!(promotion.is_promotion());
+        assert!(!promotion.is_quiet());
+        assert!(!promotion.is_capture());
+    }
+
+    #[test]
+    fn test_position_algebraic_round_trip() {
+        let e4 = Position::from_algebraic("e4").unwrap();
+        assert_eq!(e4, Position { row: 3, col: 4 });
+        assert_eq!(e4.to_algebraic(), "e4");
+        assert_eq!(
+            Position::from_algebraic("a1"),
+            Some(Position { row: 0, col: 0 })
+        );
+        assert_eq!(Position { row: 7, col: 7 }.to_algebraic(), "h8");
+
+        for square in ["", "e", "e9", "i4", "e44", "E4"] {
+            assert_eq!(Position::from_algebraic(square), None, "{}", square);
+        }
+    }
+
+    #[test]
+    fn test_best_move_for_fen() {
+        assert_eq!(
+            best_move_for_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2),
+            Ok("a1a8".to_string())
+        );
+        // Stalemated
+        assert_eq!(
+            best_move_for_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 2),
+            Ok("0000".to_string())
+        );
+        assert_eq!(
+            best_move_for_fen("8/8/8 w - - 0 1", 2),
+            Err(ParseError::BadFen(FenError::WrongRankCount(3)))
+        );
+    }
+
+    #[test]
+    fn test_position_new() {
+        assert_eq!(Position::new(3, 4), Some(Position { row: 3, col: 4 }));
+        assert_eq!(Position::new(8, 0), None);
+        assert_eq!(Position::new(0, 8), None);
+        assert!(Position { row: 7, col: 7 }.is_valid());
+        assert!(!Position { row: 7, col: 9 }.is_valid());
+    }
+
+    #[test]
+    fn test_square_display() {
+        let knight = Square::new(Piece::Knight, Some(Color::White));
+        assert_eq!(knight.to_string(), "♘");
+        assert_eq!(
+            Square::new(Piece::Queen, Some(Color::Black)).to_string(),
+            "♛"
+        );
+        assert_eq!(Square::new(Piece::Empty, None).to_string(), ".");
+    }
+}
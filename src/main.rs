@@ -4,7 +4,7 @@ use std::{
     time::{Duration, Instant},
 };
 
-use chess::Board;
+use chess::{Board, DrawReason, GameResult};
 use sysinfo::System;
 
 fn clear_screen() {
@@ -27,34 +27,111 @@ fn print_memory_usage(system: &mut System) {
     }
 }
 
+/// Command-line options for the self-play demo.
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    uci: bool,
+    /// Position to start from instead of the initial one.
+    fen: Option<String>,
+    /// Fixed search depth per move instead of the engine's default.
+    depth: Option<usize>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--uci" => options.uci = true,
+            "--fen" => options.fen = Some(args.next().ok_or("--fen needs a position")?),
+            "--depth" => {
+                let depth = args.next().ok_or("--depth needs a number")?;
+                let depth = depth
+                    .parse()
+                    .ok()
+                    .filter(|&depth| depth > 0)
+                    .ok_or_else(|| format!("invalid depth: {depth}"))?;
+                options.depth = Some(depth);
+            }
+            _ => return Err(format!("unknown argument: {arg}")),
+        }
+    }
+    Ok(options)
+}
+
+fn report_result(result: GameResult, board: &Board) {
+    match result {
+        GameResult::Checkmate { winner } => {
+            println!("{:?} wins by checkmate!", winner)
+        }
+        GameResult::Stalemate => {
+            println!("Stalemate! No legal moves for {:?}", board.turn)
+        }
+        GameResult::Draw { reason } => match reason {
+            DrawReason::FiftyMoveRule => println!("Draw by the fifty-move rule!"),
+            DrawReason::ThreefoldRepetition => {
+                println!("Draw by threefold repetition!")
+            }
+            DrawReason::InsufficientMaterial => {
+                println!("Draw by insufficient material!")
+            }
+        },
+        GameResult::Ongoing => unreachable!("the game is over"),
+    }
+}
+
 fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}");
+            eprintln!("usage: chess [--uci] [--fen \"<fen>\"] [--depth N]");
+            std::process::exit(2);
+        }
+    };
+    if options.uci {
+        chess::uci::run_uci().unwrap();
+        return;
+    }
+
     let mut system = System::new_all();
-    let mut board = Board::new();
+    let mut board = match &options.fen {
+        Some(fen) => Board::from_fen(fen).unwrap_or_else(|err| {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }),
+        None => Board::new(),
+    };
     println!("{}", board);
+    // A position given with --fen may already be over
+    if let Some(result) = board.is_game_over() {
+        report_result(result, &board);
+        return;
+    }
 
     // Simulate a simple game where the computer makes the best moves
     loop {
         print_memory_usage(&mut system);
         let start_time = Instant::now();
-        if let Some(best_move) = board.find_best_move() {
-            board.make_move(best_move.from, best_move.to);
-            clear_screen();
-            println!("{:}", board);
-            let duration = start_time.elapsed();
-            println!(
-                "Time taken to calculate move for {:?}: {:?}",
-                board.turn, duration
-            );
-            // Add a small delay to make the moves more visible
-            thread::sleep(Duration::from_millis(300));
-
-            // Check for checkmate
-            if board.is_checkmate(board.turn) {
-                println!("{:?} wins!", board.turn);
-                break;
-            }
-        } else {
-            println!("Stalemate! No valid moves for {:?}", board.turn);
+        let best_move = match options.depth {
+            Some(depth) => board.search(depth).best_move,
+            None => board.find_best_move(),
+        };
+        let best_move = best_move.expect("a game that isn't over has a legal move");
+        board
+            .try_make_move(best_move)
+            .expect("engine produced an illegal move");
+        clear_screen();
+        println!("{:}", board);
+        let duration = start_time.elapsed();
+        println!(
+            "Time taken to calculate move for {:?}: {:?}",
+            board.turn, duration
+        );
+        // Add a small delay to make the moves more visible
+        thread::sleep(Duration::from_millis(300));
+
+        if let Some(result) = board.is_game_over() {
+            report_result(result, &board);
             break;
         }
 
@@ -62,3 +139,35 @@ fn main() {
         thread::sleep(Duration::from_secs(1));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_args, Options};
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(args(&[])), Ok(Options::default()));
+
+        let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+        assert_eq!(
+            parse_args(args(&["--fen", fen, "--depth", "4"])),
+            Ok(Options {
+                uci: false,
+                fen: Some(fen.to_string()),
+                depth: Some(4),
+            })
+        );
+
+        assert!(parse_args(args(&["--depth", "deep"])).is_err());
+        assert!(parse_args(args(&["--depth", "0"])).is_err());
+        assert!(parse_args(args(&["--fen"])).is_err());
+        assert!(parse_args(args(&["--bogus"])).is_err());
+    }
+}
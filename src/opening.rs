@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use crate::{Board, Move, ParseError};
+
+/// Moves to play in known positions instead of searching. Each line of the
+/// text form is a FEN followed by a move in long algebraic notation, e.g.
+///
+/// ```text
+/// rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 e2e4
+/// ```
+///
+/// Blank lines and lines starting with `#` are skipped. A position may be
+/// listed more than once; its first move is the one played.
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    /// Booked moves keyed by position hash.
+    entries: HashMap<u64, Vec<Move>>,
+}
+
+impl Book {
+    pub fn new() -> Self {
+        Book::default()
+    }
+
+    /// Reads a book from its text form, rejecting unparsable or illegal
+    /// entries.
+    pub fn parse(text: &str) -> Result<Book, ParseError> {
+        let mut book = Book::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (fen, uci) = line
+                .rsplit_once(char::is_whitespace)
+                .ok_or_else(|| ParseError::BadUci(line.to_string()))?;
+            let board = Board::from_fen(fen.trim_end())?;
+            let mv = board.parse_uci_move(uci)?;
+            book.insert(&board, mv);
+        }
+        Ok(book)
+    }
+
+    /// Books `mv` for the position on `board`.
+    pub fn insert(&mut self, board: &Board, mv: Move) {
+        self.entries.entry(board.hash()).or_default().push(mv);
+    }
+
+    /// Number of positions with at least one booked move.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Board {
+    /// The book's move for the current position, if it has one that is
+    /// legal here.
+    pub fn book_move(&self, book: &Book) -> Option<Move> {
+        let booked = book.entries.get(&self.hash())?;
+        let legal = self.get_legal_moves(self.turn);
+        booked.iter().find_map(|mv| {
+            legal
+                .iter()
+                .find(|legal| {
+                    legal.from == mv.from && legal.to == mv.to && legal.promotion == mv.promotion
+                })
+                .copied()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{opening::Book, Board, ParseError};
+
+    const BOOK: &str = "\
+# King's pawn
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 e2e4
+rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 c7c5
+";
+
+    #[test]
+    fn test_book_move() {
+        let book = Book::parse(BOOK).unwrap();
+        assert_eq!(book.len(), 2);
+
+        let mut board = Board::new();
+        let mv = board.book_move(&book).unwrap();
+        assert_eq!(board.move_to_uci(&mv), "e2e4");
+
+        board.make_move(mv);
+        let reply = board.book_move(&book).unwrap();
+        assert_eq!(board.move_to_uci(&reply), "c7c5");
+
+        board.make_move(reply);
+        assert_eq!(board.book_move(&book), None);
+    }
+
+    #[test]
+    fn test_find_best_move_plays_from_the_book() {
+        // Not a move the search would choose
+        let book =
+            Book::parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 g1h3").unwrap();
+        let mut board = Board::new();
+        board.set_book(book);
+        let mv = board.find_best_move().unwrap();
+        assert_eq!(board.move_to_uci(&mv), "g1h3");
+        assert_eq!(board.nodes_searched(), 0);
+    }
+
+    #[test]
+    fn test_parse_book_errors() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(
+            Book::parse(&format!("{} e2e5", start)).unwrap_err(),
+            ParseError::IllegalMove("e2e5".to_string())
+        );
+        assert!(matches!(
+            Book::parse("not a fen e2e4"),
+            Err(ParseError::BadFen(_))
+        ));
+        assert!(Book::parse("\n# nothing here\n").unwrap().is_empty());
+    }
+}
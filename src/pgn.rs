@@ -0,0 +1,399 @@
+use std::fmt;
+
+use crate::{san::san_tokens, Board, Color, GameResult, Move, ParseError};
+
+/// The tags every PGN game carries, in the order they are written.
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+/// Movetext lines are wrapped before they get longer than this.
+const LINE_WIDTH: usize = 80;
+
+/// A game in Portable Game Notation terms: its tags and the moves played.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Game {
+    /// Tag pairs in the order they appeared, e.g. `("White", "Carlsen")`.
+    pub tags: Vec<(String, String)>,
+    /// The moves of the main line, starting from the initial position.
+    pub moves: Vec<Move>,
+}
+
+/// How a played move compares with the engine's choice in the same position.
+/// Scores are from the mover's point of view, in the evaluation's units of a
+/// tenth of a pawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveAnalysis {
+    pub played: Move,
+    pub played_score: i32,
+    pub best_move: Option<Move>,
+    pub best_score: i32,
+    /// How much worse the played move is than the best one; never negative.
+    pub loss: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    /// A tag pair line that isn't of the form `[Name "Value"]`.
+    BadTag(String),
+    /// A comment or variation that is never closed.
+    Unterminated(char),
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::BadTag(line) => write!(f, "malformed tag pair '{}'", line),
+            PgnError::Unterminated(open) => write!(f, "unterminated '{}'", open),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl Game {
+    /// The value of the tag called `name`, if present.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The position after all the moves have been played.
+    pub fn board(&self) -> Board {
+        let mut board = Board::new();
+        for mv in &self.moves {
+            board.make_move(*mv);
+        }
+        board
+    }
+
+    /// Every position of the game in order: the one before each move, then
+    /// the final one.
+    pub fn positions(&self) -> impl Iterator<Item = Board> + '_ {
+        let start = Board::new();
+        std::iter::once(start.clone()).chain(self.moves.iter().scan(start, |board, mv| {
+            board.make_move(*mv);
+            Some(board.clone())
+        }))
+    }
+
+    /// Searches every position of the game to `depth` and compares each
+    /// played move with the best one found.
+    pub fn analyze(&self, depth: usize) -> Vec<MoveAnalysis> {
+        self.positions()
+            .zip(&self.moves)
+            .map(|(mut board, &played)| {
+                let best = board.search(depth);
+                let played_score = board.score_move(played, depth);
+                MoveAnalysis {
+                    played,
+                    played_score,
+                    best_move: best.best_move,
+                    best_score: best.score,
+                    loss: (best.score - played_score).max(0),
+                }
+            })
+            .collect()
+    }
+
+    /// Writes the game as PGN: the seven tag roster (with `?` placeholders
+    /// for missing tags), any other tags, then the numbered SAN movetext.
+    /// The result comes from the final position when the game is over on
+    /// the board, and from the `Result` tag otherwise.
+    pub fn to_pgn(&self) -> String {
+        let mut board = Board::new();
+        let mut tokens = Vec::new();
+        for mv in &self.moves {
+            if board.turn == Color::White {
+                tokens.push(format!("{}.", board.fullmove_number));
+            } else if tokens.is_empty() {
+                tokens.push(format!("{}...", board.fullmove_number));
+            }
+            tokens.push(board.move_to_san(mv));
+            board.make_move(*mv);
+        }
+        let result = match board.result() {
+            GameResult::Checkmate {
+                winner: Color::White,
+            } => "1-0",
+            GameResult::Checkmate {
+                winner: Color::Black,
+            } => "0-1",
+            GameResult::Stalemate | GameResult::Draw { .. } => "1/2-1/2",
+            GameResult::Ongoing => self.tag("Result").unwrap_or("*"),
+        };
+        tokens.push(result.to_string());
+
+        let mut pgn = String::new();
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => result,
+                _ => self.tag(name).unwrap_or(default),
+            };
+            push_tag(&mut pgn, name, value);
+        }
+        for (name, value) in &self.tags {
+            if !SEVEN_TAG_ROSTER.iter().any(|(roster, _)| roster == name) {
+                push_tag(&mut pgn, name, value);
+            }
+        }
+        pgn.push('\n');
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > LINE_WIDTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            line_length += token.len();
+            pgn.push_str(&token);
+        }
+        pgn.push('\n');
+        pgn
+    }
+}
+
+fn push_tag(pgn: &mut String, name: &str, value: &str) {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    pgn.push_str(&format!("[{} \"{}\"]\n", name, escaped));
+}
+
+/// Parses a single game. Comments, NAGs and variations are skipped; only the
+/// main line is kept. Malformed tags and annotations are
+/// `ParseError::BadPgn`; a bad move fails as it would in `parse_san`.
+pub fn parse_pgn(text: &str) -> Result<Game, ParseError> {
+    let mut tags = Vec::new();
+    let mut movetext = String::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') && movetext.trim().is_empty() {
+            tags.push(parse_tag(line)?);
+        } else if !line.starts_with('%') {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+
+    let mut board = Board::new();
+    let mut moves = Vec::new();
+    for san in san_tokens(&strip_annotations(&movetext)?) {
+        let mv = board.parse_san(san)?;
+        board.make_move(mv);
+        moves.push(mv);
+    }
+
+    Ok(Game { tags, moves })
+}
+
+/// Parses `[Name "Value"]`, unescaping `\"` and `\\` in the value.
+fn parse_tag(line: &str) -> Result<(String, String), PgnError> {
+    let bad_tag = || PgnError::BadTag(line.to_string());
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(bad_tag)?;
+    let (name, value) = inner
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(bad_tag)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or_else(bad_tag)?;
+
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            unescaped.extend(chars.next());
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Ok((name.to_string(), unescaped))
+}
+
+/// Drops `{...}` and `;` comments, `(...)` variations and `$n` NAGs.
+fn strip_annotations(movetext: &str) -> Result<String, PgnError> {
+    let mut result = String::new();
+    let mut chars = movetext.chars();
+    let mut variation_depth = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                if !chars.by_ref().any(|c| c == '}') {
+                    return Err(PgnError::Unterminated('{'));
+                }
+                result.push(' ');
+            }
+            ';' => {
+                chars.by_ref().find(|&c| c == '\n');
+                result.push(' ');
+            }
+            '(' => variation_depth += 1,
+            ')' if variation_depth > 0 => {
+                variation_depth -= 1;
+                result.push(' ');
+            }
+            '$' => {
+                chars = chars
+                    .as_str()
+                    .trim_start_matches(|c: char| c.is_ascii_digit())
+                    .chars();
+                result.push(' ');
+            }
+            _ if variation_depth > 0 => {}
+            _ => result.push(c),
+        }
+    }
+    if variation_depth > 0 {
+        return Err(PgnError::Unterminated('('));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_pgn;
+    use crate::{
+        board::MATE_THRESHOLD,
+        pgn::{Game, PgnError},
+        Board, Color, ParseError, Piece,
+    };
+
+    const SCHOLARS_MATE: &str = r#"[Event "Casual game"]
+[Site "?"]
+[Date "2024.01.01"]
+[Round "1"]
+[White "Alice \"the Quick\""]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 {The king's pawn} e5 2. Qh5 $2 Nc6 3. Bc4 Nf6?? (3... g6 4. Qf3)
+4. Qxf7# ; mate
+1-0
+"#;
+
+    #[test]
+    fn test_parse_annotated_game() {
+        let game = parse_pgn(SCHOLARS_MATE).unwrap();
+        assert_eq!(game.tags.len(), 7);
+        assert_eq!(game.tag("White"), Some("Alice \"the Quick\""));
+        assert_eq!(game.tag("Result"), Some("1-0"));
+        assert_eq!(game.moves.len(), 7);
+
+        let board = game.board();
+        assert_eq!(
+            board.to_fen(),
+            "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4"
+        );
+        assert!(board.is_checkmate(Color::Black));
+    }
+
+    #[test]
+    fn test_positions() {
+        let game = parse_pgn(SCHOLARS_MATE).unwrap();
+        let positions: Vec<Board> = game.positions().collect();
+        assert_eq!(positions.len(), game.moves.len() + 1);
+        assert_eq!(positions[0], Board::new());
+        assert_eq!(positions.last(), Some(&game.board()));
+        for (before, mv) in positions.iter().zip(&game.moves) {
+            assert_eq!(before.squares[mv.from.row][mv.from.col].piece, mv.piece);
+        }
+    }
+
+    #[test]
+    fn test_analyze_flags_a_hanging_queen() {
+        let game = parse_pgn("1. e4 d5 2. Qg4 *").unwrap();
+        let analysis = game.analyze(2);
+        assert_eq!(analysis.len(), 3);
+        assert_eq!(analysis[2].played, game.moves[2]);
+
+        // Bxg4 wins the queen
+        let blunder = &analysis[2];
+        assert!(blunder.loss >= Piece::Queen.value() / 2, "{:?}", blunder);
+        assert!(analysis[0].loss < blunder.loss);
+    }
+
+    #[test]
+    fn test_analyze_scores_the_best_move_without_loss() {
+        let game = parse_pgn("1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0").unwrap();
+        for depth in 1..=3 {
+            let analysis = game.analyze(depth);
+            for entry in &analysis {
+                if entry.best_move == Some(entry.played) {
+                    assert_eq!(entry.loss, 0, "depth {}: {:?}", depth, entry);
+                    assert_eq!(entry.played_score, entry.best_score);
+                }
+            }
+            let mate = analysis.last().unwrap();
+            assert_eq!(mate.best_move, Some(mate.played), "depth {}", depth);
+            assert!(mate.played_score >= MATE_THRESHOLD, "depth {}", depth);
+            assert_eq!(mate.loss, 0);
+        }
+    }
+
+    #[test]
+    fn test_parse_pgn_errors() {
+        assert_eq!(
+            parse_pgn("[Event Casual]\n\n1. e4"),
+            Err(ParseError::BadPgn(PgnError::BadTag(
+                "[Event Casual]".to_string()
+            )))
+        );
+        assert_eq!(
+            parse_pgn("1. e4 {never closed"),
+            Err(ParseError::BadPgn(PgnError::Unterminated('{')))
+        );
+        assert_eq!(
+            parse_pgn("1. e4 e4"),
+            Err(ParseError::IllegalMove("e4".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_pgn_round_trip() {
+        let game = parse_pgn(SCHOLARS_MATE).unwrap();
+        let pgn = game.to_pgn();
+        assert!(pgn.starts_with("[Event \"Casual game\"]\n"));
+        assert!(pgn.contains("[White \"Alice \\\"the Quick\\\"\"]\n"));
+        assert!(pgn.ends_with("\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n"));
+
+        assert_eq!(parse_pgn(&pgn).unwrap(), game);
+    }
+
+    #[test]
+    fn test_to_pgn_fills_in_roster_and_wraps() {
+        let mut board = Board::new();
+        let mut game = Game::default();
+        // Shuffle the knights back and forth long enough to need wrapping
+        for _ in 0..6 {
+            for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+                let mv = board.parse_san(san).unwrap();
+                board.make_move(mv);
+                game.moves.push(mv);
+            }
+        }
+
+        let pgn = game.to_pgn();
+        assert!(pgn.starts_with("[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n"));
+        // Threefold repetition
+        assert!(pgn.contains("[Result \"1/2-1/2\"]\n"));
+        assert!(pgn.trim_end().ends_with("1/2-1/2"));
+        let movetext = pgn.split("\n\n").nth(1).unwrap();
+        assert!(movetext.lines().count() > 1);
+        assert!(movetext.lines().all(|line| line.len() <= super::LINE_WIDTH));
+        assert_eq!(parse_pgn(&pgn).unwrap().moves, game.moves);
+    }
+}
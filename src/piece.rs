@@ -1,4 +1,5 @@
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Piece {
     King,
     Queen,
@@ -76,6 +77,18 @@ impl Piece {
         [2, 3, 1, 0, 0, 1, 3, 2],
     ];
 
+    /// Once the heavy pieces are gone the king should head for the center.
+    pub const KING_ENDGAME_TABLE: [[i32; 8]; 8] = [
+        [-5, -4, -3, -2, -2, -3, -4, -5],
+        [-3, -2, -1, 0, 0, -1, -2, -3],
+        [-3, -1, 2, 3, 3, 2, -1, -3],
+        [-3, -1, 3, 4, 4, 3, -1, -3],
+        [-3, -1, 3, 4, 4, 3, -1, -3],
+        [-3, -1, 2, 3, 3, 2, -1, -3],
+        [-3, -3, 0, 0, 0, 0, -3, -3],
+        [-5, -3, -3, -3, -3, -3, -3, -5],
+    ];
+
     pub fn directions(&self) -> Vec<(isize, isize)> {
         match self {
             Piece::King => vec![
@@ -0,0 +1,445 @@
+use std::fmt;
+
+use crate::{board::CastlingSide, piece::Piece, Board, Move, ParseError, Position};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SanError {
+    /// The text isn't well-formed SAN.
+    Invalid(String),
+    /// More than one legal move matches the SAN.
+    Ambiguous(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::Invalid(san) => write!(f, "'{}' is not valid SAN", san),
+            SanError::Ambiguous(san) => write!(f, "'{}' matches more than one move", san),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+fn letter_piece(letter: char) -> Option<Piece> {
+    match letter {
+        'K' => Some(Piece::King),
+        'Q' => Some(Piece::Queen),
+        'R' => Some(Piece::Rook),
+        'B' => Some(Piece::Bishop),
+        'N' => Some(Piece::Knight),
+        _ => None,
+    }
+}
+
+fn piece_letter(piece: Piece) -> Option<char> {
+    match piece {
+        Piece::King => Some('K'),
+        Piece::Queen => Some('Q'),
+        Piece::Rook => Some('R'),
+        Piece::Bishop => Some('B'),
+        Piece::Knight => Some('N'),
+        Piece::Pawn | Piece::Empty => None,
+    }
+}
+
+/// The SAN moves in a line of movetext, without move numbers or the result.
+pub(crate) fn san_tokens(movetext: &str) -> impl Iterator<Item = &str> {
+    movetext
+        .split_whitespace()
+        .filter(|token| !matches!(*token, "1-0" | "0-1" | "1/2-1/2" | "*"))
+        // Move numbers, either on their own (`1.`, `1...`) or glued to the
+        // move (`1.e4`). Careful not to eat the digits of `0-0`.
+        .map(|token| {
+            let digits = token.len() - token.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if token[digits..].starts_with('.') {
+                token[digits..].trim_start_matches('.')
+            } else {
+                token
+            }
+        })
+        .filter(|san| !san.is_empty())
+}
+
+impl Board {
+    /// Formats a legal move in Standard Algebraic Notation, e.g. `Nf3`,
+    /// `exd5`, `O-O` or `e8=Q+`.
+    pub fn move_to_san(&self, mv: &Move) -> String {
+        let mut san = String::new();
+
+        if let Some(side) = self.castling_side(mv) {
+            san.push_str(match side {
+                CastlingSide::Kingside => "O-O",
+                CastlingSide::Queenside => "O-O-O",
+            });
+        } else {
+            match piece_letter(mv.piece) {
+                Some(letter) => {
+                    san.push(letter);
+                    san.push_str(&self.disambiguation(mv));
+                }
+                None => {
+                    if mv.is_capture() {
+                        san.push((b'a' + mv.from.col as u8) as char);
+                    }
+                }
+            }
+            if mv.is_capture() {
+                san.push('x');
+            }
+            san.push_str(&mv.to.to_algebraic());
+            if let Some(promotion) = mv.promotion.and_then(piece_letter) {
+                san.push('=');
+                san.push(promotion);
+            }
+        }
+
+        if self.gives_check(mv) {
+            san.push(if self.clone().gives_checkmate(mv) {
+                '#'
+            } else {
+                '+'
+            });
+        }
+
+        san
+    }
+
+    /// Resolves a SAN token such as `Nbd2`, `O-O-O` or `exd8=Q+` against the
+    /// legal moves of the side to move. Text that isn't SAN, or that
+    /// matches several moves, is `ParseError::BadSan`; SAN that matches no
+    /// legal move is `ParseError::IllegalMove`.
+    pub fn parse_san(&self, san: &str) -> Result<Move, ParseError> {
+        let invalid = || ParseError::BadSan(SanError::Invalid(san.to_string()));
+        let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        let legal_moves = self.get_legal_moves(self.turn);
+
+        let castle = match text {
+            "O-O" | "0-0" => Some(CastlingSide::Kingside),
+            "O-O-O" | "0-0-0" => Some(CastlingSide::Queenside),
+            _ => None,
+        };
+        let candidates: Vec<Move> = if castle.is_some() {
+            legal_moves
+                .into_iter()
+                .filter(|mv| self.castling_side(mv) == castle)
+                .collect()
+        } else {
+            let mut chars: Vec<char> = text.chars().collect();
+
+            let piece = match chars.first().copied().and_then(letter_piece) {
+                Some(piece) => {
+                    chars.remove(0);
+                    piece
+                }
+                None => Piece::Pawn,
+            };
+
+            // Promotion, written `e8=Q` or `e8Q`
+            let promotion = match chars.last().copied().and_then(letter_piece) {
+                Some(promoted) if piece == Piece::Pawn => {
+                    chars.pop();
+                    if chars.last() == Some(&'=') {
+                        chars.pop();
+                    }
+                    Some(promoted)
+                }
+                _ => None,
+            };
+
+            if chars.len() < 2 {
+                return Err(invalid());
+            }
+            let destination: String = chars.split_off(chars.len() - 2).into_iter().collect();
+            let to = Position::from_algebraic(&destination).ok_or_else(invalid)?;
+
+            let is_capture = chars.last() == Some(&'x');
+            if is_capture {
+                chars.pop();
+            }
+            let mut from_col = None;
+            let mut from_row = None;
+            for c in chars {
+                match c {
+                    'a'..='h' if from_col.is_none() => from_col = Some(c as usize - 'a' as usize),
+                    '1'..='8' if from_row.is_none() => from_row = Some(c as usize - '1' as usize),
+                    _ => return Err(invalid()),
+                }
+            }
+
+            legal_moves
+                .into_iter()
+                .filter(|mv| {
+                    mv.piece == piece
+                        && mv.to == to
+                        && mv.promotion == promotion
+                        && (!is_capture || mv.is_capture())
+                        && from_col.is_none_or(|col| mv.from.col == col)
+                        && from_row.is_none_or(|row| mv.from.row == row)
+                        // Castling is only ever spelled O-O / O-O-O
+                        && self.castling_side(mv).is_none()
+                })
+                .collect()
+        };
+
+        match candidates.as_slice() {
+            [mv] => Ok(*mv),
+            [] => Err(ParseError::IllegalMove(san.to_string())),
+            _ => Err(SanError::Ambiguous(san.to_string()).into()),
+        }
+    }
+
+    /// Plays a line of SAN moves such as `1. e4 e5 2. Nf3`. Move numbers and
+    /// a trailing result are skipped. Stops at the first move that doesn't
+    /// parse, leaving the moves before it on the board.
+    pub fn apply_san_line(&mut self, moves: &str) -> Result<(), ParseError> {
+        for san in san_tokens(moves) {
+            let mv = self.parse_san(san)?;
+            self.make_move(mv);
+        }
+        Ok(())
+    }
+
+    /// The origin file and/or rank needed when another piece of the same
+    /// kind can also reach the destination square.
+    fn disambiguation(&self, mv: &Move) -> String {
+        let rivals: Vec<Move> = self
+            .get_legal_moves(self.turn)
+            .into_iter()
+            .filter(|other| other.piece == mv.piece && other.to == mv.to && other.from != mv.from)
+            .collect();
+        if rivals.is_empty() {
+            return String::new();
+        }
+
+        let file = (b'a' + mv.from.col as u8) as char;
+        let rank = (b'1' + mv.from.row as u8) as char;
+        if rivals.iter().all(|other| other.from.col != mv.from.col) {
+            file.to_string()
+        } else if rivals.iter().all(|other| other.from.row != mv.from.row) {
+            rank.to_string()
+        } else {
+            format!("{}{}", file, rank)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, Color, Move, ParseError, Piece, Position, SanError};
+
+    fn legal_move(board: &Board, from: Position, to: Position, promotion: Option<Piece>) -> Move {
+        board
+            .get_legal_moves(board.turn)
+            .into_iter()
+            .find(|mv| mv.from == from && mv.to == to && mv.promotion == promotion)
+            .expect("move should be legal")
+    }
+
+    #[test]
+    fn test_san_pawn_capture() {
+        let board =
+            Board::from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2")
+                .unwrap();
+        let mv = legal_move(
+            &board,
+            Position { row: 3, col: 4 },
+            Position { row: 4, col: 3 },
+            None,
+        );
+        assert_eq!(board.move_to_san(&mv), "exd5");
+    }
+
+    #[test]
+    fn test_san_disambiguates_knights() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap();
+        let mv = legal_move(
+            &board,
+            Position { row: 0, col: 1 },
+            Position { row: 1, col: 3 },
+            None,
+        );
+        assert_eq!(board.move_to_san(&mv), "Nbd2");
+
+        let board = Board::from_fen("4k3/8/8/N7/8/8/8/N3K3 w - - 0 1").unwrap();
+        let mv = legal_move(
+            &board,
+            Position { row: 0, col: 0 },
+            Position { row: 2, col: 1 },
+            None,
+        );
+        assert_eq!(board.move_to_san(&mv), "N1b3");
+    }
+
+    #[test]
+    fn test_san_castling() {
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let kingside = legal_move(
+            &board,
+            Position { row: 0, col: 4 },
+            Position { row: 0, col: 6 },
+            None,
+        );
+        let queenside = legal_move(
+            &board,
+            Position { row: 0, col: 4 },
+            Position { row: 0, col: 2 },
+            None,
+        );
+        assert_eq!(board.move_to_san(&kingside), "O-O");
+        assert_eq!(board.move_to_san(&queenside), "O-O-O");
+    }
+
+    #[test]
+    fn test_san_promotion_with_check() {
+        let board = Board::from_fen("7k/4P3/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        let mv = legal_move(
+            &board,
+            Position { row: 6, col: 4 },
+            Position { row: 7, col: 4 },
+            Some(Piece::Queen),
+        );
+        assert_eq!(board.move_to_san(&mv), "e8=Q+");
+    }
+
+    #[test]
+    fn test_san_checkmate() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let mv = legal_move(
+            &board,
+            Position { row: 0, col: 0 },
+            Position { row: 7, col: 0 },
+            None,
+        );
+        assert_eq!(board.move_to_san(&mv), "Ra8#");
+    }
+
+    #[test]
+    fn test_san_discovered_check() {
+        // The knight steps off the rook's file without attacking the king
+        let board = Board::from_fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1").unwrap();
+        let mv = legal_move(
+            &board,
+            Position { row: 3, col: 4 },
+            Position { row: 2, col: 2 },
+            None,
+        );
+        assert_eq!(board.move_to_san(&mv), "Nc3+");
+    }
+
+    #[test]
+    fn test_san_double_check_mate() {
+        // Knight and rook both check, and the king has no flight square
+        let board = Board::from_fen("3rkb2/3p1p2/8/8/4N3/8/8/4R1K1 w - - 0 1").unwrap();
+        let mv = legal_move(
+            &board,
+            Position { row: 3, col: 4 },
+            Position { row: 5, col: 5 },
+            None,
+        );
+        assert_eq!(board.move_to_san(&mv), "Nf6#");
+    }
+
+    #[test]
+    fn test_parse_san_pawn_push() {
+        let board = Board::new();
+        assert_eq!(
+            board.parse_san("e4"),
+            Ok(Move {
+                from: Position { row: 1, col: 4 },
+                to: Position { row: 3, col: 4 },
+                piece: Piece::Pawn,
+                captured: None,
+                promotion: None,
+                score: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_san_disambiguated_knight() {
+        let board = Board::from_fen("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+        assert_eq!(
+            board.parse_san("Nbd2"),
+            Ok(Move {
+                from: Position { row: 0, col: 1 },
+                to: Position { row: 1, col: 3 },
+                piece: Piece::Knight,
+                captured: None,
+                promotion: None,
+                score: 0,
+            })
+        );
+        assert_eq!(
+            board.parse_san("Nd2"),
+            Err(ParseError::BadSan(SanError::Ambiguous("Nd2".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_parse_san_queenside_castling() {
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+        assert_eq!(
+            board.parse_san("O-O-O"),
+            Ok(Move {
+                from: Position { row: 7, col: 4 },
+                to: Position { row: 7, col: 2 },
+                piece: Piece::King,
+                captured: None,
+                promotion: None,
+                score: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_san_capture_promotion_with_check() {
+        let board = Board::from_fen("k2r4/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            board.parse_san("exd8=Q+"),
+            Ok(Move {
+                from: Position { row: 6, col: 4 },
+                to: Position { row: 7, col: 3 },
+                piece: Piece::Pawn,
+                captured: Some(Piece::Rook),
+                promotion: Some(Piece::Queen),
+                score: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_san_rejects_illegal_and_garbage() {
+        let board = Board::new();
+        assert_eq!(
+            board.parse_san("e5"),
+            Err(ParseError::IllegalMove("e5".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("Zz9"),
+            Err(ParseError::BadSan(SanError::Invalid("Zz9".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_apply_san_line_italian_game() {
+        let mut board = Board::new();
+        board
+            .apply_san_line("1. e4 e5 2. Nf3 Nc6 3.Bc4 Bc5 4. 0-0 Nf6 *")
+            .unwrap();
+        assert_eq!(
+            board.to_fen(),
+            "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 w kq - 6 5"
+        );
+    }
+
+    #[test]
+    fn test_apply_san_line_stops_at_bad_move() {
+        let mut board = Board::new();
+        assert_eq!(
+            board.apply_san_line("1. e4 e4"),
+            Err(ParseError::IllegalMove("e4".to_string()))
+        );
+        assert_eq!(board.turn, Color::Black);
+    }
+}
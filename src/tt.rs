@@ -0,0 +1,222 @@
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+
+use crate::{board::MATE_THRESHOLD, Move};
+
+/// Entries kept when no size has been set.
+pub(crate) const DEFAULT_CAPACITY: usize = 1 << 16;
+
+/// How a cached score relates to the true value of its position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Bound {
+    Exact,
+    /// The search failed high; the true score is at least this.
+    Lower,
+    /// The search failed low; the true score is at most this.
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TtEntry {
+    pub(crate) depth: usize,
+    pub(crate) score: i32,
+    pub(crate) flag: Bound,
+    pub(crate) best_move: Option<Move>,
+}
+
+/// Mate scores count plies from the root, but a cached position can be
+/// reached at any ply, so the table stores them counted from the position.
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score > MATE_THRESHOLD {
+        score + ply as i32
+    } else if score < -MATE_THRESHOLD {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score > MATE_THRESHOLD {
+        score - ply as i32
+    } else if score < -MATE_THRESHOLD {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+impl TtEntry {
+    pub(crate) fn new(
+        depth: usize,
+        score: i32,
+        alpha: i32,
+        beta: i32,
+        best_move: Option<Move>,
+        ply: usize,
+    ) -> Self {
+        let flag = if score <= alpha {
+            Bound::Upper
+        } else if score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        TtEntry {
+            depth,
+            score: score_to_tt(score, ply),
+            flag,
+            best_move,
+        }
+    }
+
+    /// The cached score, if it settles a search to `depth` in this window.
+    pub(crate) fn usable_score(
+        &self,
+        depth: usize,
+        alpha: i32,
+        beta: i32,
+        ply: usize,
+    ) -> Option<i32> {
+        if self.depth < depth {
+            return None;
+        }
+        let score = score_from_tt(self.score, ply);
+        match self.flag {
+            Bound::Exact => Some(score),
+            Bound::Lower if score >= beta => Some(score),
+            Bound::Upper if score <= alpha => Some(score),
+            _ => None,
+        }
+    }
+}
+
+/// A fixed number of slots indexed by position hash. A new entry always
+/// replaces whatever was in its slot, so memory stays bounded while the
+/// table keeps up with the current search. Handles made with `share` use
+/// the same slots, so the threads of a parallel search can pool results.
+/// Each slot has its own lock, so threads only wait on each other when they
+/// touch the same slot at the same moment.
+pub(crate) struct TranspositionTable {
+    /// Allocated on first use, since most boards never search.
+    slots: Arc<OnceLock<Box<[Mutex<Slot>]>>>,
+    capacity: usize,
+}
+
+type Slot = Option<(u64, TtEntry)>;
+
+/// The entries are plain data, so a thread that panicked while holding a
+/// slot's lock can't have left it inconsistent.
+fn lock(slot: &Mutex<Slot>) -> MutexGuard<'_, Slot> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl TranspositionTable {
+    pub(crate) fn new(capacity: usize) -> Self {
+        TranspositionTable {
+            slots: Arc::new(OnceLock::new()),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Another handle to the same table.
+    pub(crate) fn share(&self) -> Self {
+        TranspositionTable {
+            slots: Arc::clone(&self.slots),
+            capacity: self.capacity,
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.slots.get().map_or(0, |slots| {
+            slots.iter().filter(|slot| lock(slot).is_some()).count()
+        })
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn index(&self, hash: u64) -> usize {
+        (hash % self.capacity as u64) as usize
+    }
+
+    pub(crate) fn get(&self, hash: u64) -> Option<TtEntry> {
+        let slot = self.slots.get()?.get(self.index(hash))?;
+        match *lock(slot) {
+            Some((key, entry)) if key == hash => Some(entry),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn insert(&mut self, hash: u64, entry: TtEntry) {
+        let slots = self
+            .slots
+            .get_or_init(|| (0..self.capacity).map(|_| Mutex::new(None)).collect());
+        *lock(&slots[self.index(hash)]) = Some((hash, entry));
+    }
+
+    pub(crate) fn clear(&mut self) {
+        match Arc::get_mut(&mut self.slots) {
+            // Nobody else holds the table, so its memory can go
+            Some(slots) => *slots = OnceLock::new(),
+            None => {
+                for slot in self.slots.get().into_iter().flatten() {
+                    *lock(slot) = None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bound, TranspositionTable, TtEntry};
+
+    fn entry(depth: usize) -> TtEntry {
+        TtEntry {
+            depth,
+            score: 0,
+            flag: Bound::Exact,
+            best_move: None,
+        }
+    }
+
+    #[test]
+    fn test_table_stays_within_capacity() {
+        let mut table = TranspositionTable::new(100);
+        for hash in 0..1_000u64 {
+            table.insert(hash.wrapping_mul(0x9e37_79b9_7f4a_7c15), entry(1));
+            assert!(table.len() <= 100);
+        }
+        assert!(!table.is_empty());
+
+        // The latest entry always goes in
+        table.insert(12_345, entry(7));
+        assert_eq!(table.get(12_345).map(|entry| entry.depth), Some(7));
+        // A different position in the same slot is a miss, not a false hit
+        assert!(table.get(12_345 + 100).is_none());
+
+        table.clear();
+        assert!(table.is_empty());
+        assert!(table.get(12_345).is_none());
+    }
+
+    #[test]
+    fn test_shared_handles_see_each_other() {
+        let mut table = TranspositionTable::new(100);
+        let mut other = table.share();
+        std::thread::scope(|scope| {
+            scope.spawn(|| other.insert(7, entry(3)));
+        });
+        assert_eq!(table.get(7).map(|entry| entry.depth), Some(3));
+
+        table.clear();
+        assert!(other.is_empty());
+    }
+}
@@ -0,0 +1,219 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{
+    board::{MATE, MATE_THRESHOLD, MAX_DEPTH},
+    piece::Piece,
+    Board, Move, ParseError, Position, SearchInfo,
+};
+
+/// Runs the Universal Chess Interface protocol on stdin/stdout until `quit`.
+pub fn run_uci() -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    run_uci_with(stdin.lock(), stdout.lock())
+}
+
+/// Runs the UCI loop over arbitrary streams, mainly so it can be scripted.
+pub fn run_uci_with<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()> {
+    let mut board = Board::new();
+
+    for line in input.lines() {
+        let line = line?;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                writeln!(
+                    output,
+                    "id name {} {}",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                )?;
+                writeln!(
+                    output,
+                    "id author the {} developers",
+                    env!("CARGO_PKG_NAME")
+                )?;
+                writeln!(output, "uciok")?;
+            }
+            Some("isready") => writeln!(output, "readyok")?,
+            Some("ucinewgame") => board = Board::new(),
+            Some("position") => {
+                let args: Vec<&str> = tokens.collect();
+                if let Some(position) = parse_position(&args) {
+                    board = position;
+                }
+            }
+            Some("go") => {
+                let mut written = Ok(());
+                let result = board.search_with_callback(MAX_DEPTH, |info| {
+                    if written.is_ok() {
+                        written = writeln!(output, "{}", format_info(&info));
+                    }
+                });
+                written?;
+                let best_move = result
+                    .best_move
+                    .map(|mv| uci_move(&mv))
+                    .unwrap_or_else(|| "0000".to_string());
+                writeln!(output, "bestmove {}", best_move)?;
+            }
+            Some("quit") => break,
+            _ => {} // Unknown commands are ignored, as the protocol asks
+        }
+        output.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Formats a progress report as a UCI `info` line.
+fn format_info(info: &SearchInfo) -> String {
+    // Scores are in tenths of a pawn; UCI wants hundredths
+    let score = if info.score.abs() >= MATE_THRESHOLD {
+        let moves = (MATE - info.score.abs() + 1) / 2;
+        format!("mate {}", moves * info.score.signum())
+    } else {
+        format!("cp {}", info.score * 10)
+    };
+    let pv: Vec<String> = info.pv.iter().map(uci_move).collect();
+    format!(
+        "info depth {} score {} nodes {} pv {}",
+        info.depth,
+        score,
+        info.nodes,
+        pv.join(" ")
+    )
+}
+
+fn uci_move(mv: &Move) -> String {
+    let promotion = match mv.promotion {
+        Some(Piece::Queen) => "q",
+        Some(Piece::Rook) => "r",
+        Some(Piece::Bishop) => "b",
+        Some(Piece::Knight) => "n",
+        _ => "",
+    };
+    format!(
+        "{}{}{}",
+        mv.from.to_algebraic(),
+        mv.to.to_algebraic(),
+        promotion
+    )
+}
+
+/// Handles the arguments of `position [startpos | fen <fen>] [moves <m1> ...]`.
+fn parse_position(args: &[&str]) -> Option<Board> {
+    let moves_at = args.iter().position(|&arg| arg == "moves");
+    let (setup, moves) = match moves_at {
+        Some(index) => (&args[..index], &args[index + 1..]),
+        None => (args, &[][..]),
+    };
+
+    let mut board = match setup.split_first() {
+        Some((&"startpos", _)) => Board::new(),
+        Some((&"fen", fen)) => Board::from_fen(&fen.join(" ")).ok()?,
+        _ => return None,
+    };
+    for token in moves {
+        let mv = board.parse_uci_move(token).ok()?;
+        board.make_move(mv);
+    }
+    Some(board)
+}
+
+impl Board {
+    /// Resolves a long algebraic move like `e2e4` or `e7e8q` against the
+    /// legal moves of the side to move.
+    pub fn parse_uci_move(&self, text: &str) -> Result<Move, ParseError> {
+        let bad = || ParseError::BadUci(text.to_string());
+        let square = |range: std::ops::Range<usize>| {
+            let name = text.get(range).ok_or_else(bad)?;
+            name.parse::<Position>()
+        };
+        let from = square(0..2)?;
+        let to = square(2..4)?;
+        let promotion = match text.get(4..) {
+            Some("q") => Some(Piece::Queen),
+            Some("r") => Some(Piece::Rook),
+            Some("b") => Some(Piece::Bishop),
+            Some("n") => Some(Piece::Knight),
+            Some("") => None,
+            _ => return Err(bad()),
+        };
+        self.get_legal_moves(self.turn)
+            .into_iter()
+            .find(|mv| mv.from == from && mv.to == to && mv.promotion == promotion)
+            .ok_or_else(|| ParseError::IllegalMove(text.to_string()))
+    }
+
+    /// Formats `mv` in long algebraic notation, e.g. `e2e4`, `e1g1` for
+    /// castling or `e7e8q`.
+    pub fn move_to_uci(&self, mv: &Move) -> String {
+        uci_move(mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, ParseError, Piece, Position};
+
+    #[test]
+    fn test_parse_uci_move() {
+        let at = |square| Position::from_algebraic(square).unwrap();
+
+        let board = Board::new();
+        let push = board.parse_uci_move("e2e4").unwrap();
+        assert_eq!((push.from, push.to), (at("e2"), at("e4")));
+        assert_eq!(push.piece, Piece::Pawn);
+        assert!(push.is_quiet());
+
+        let board = Board::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        let capture = board.parse_uci_move("e4d5").unwrap();
+        assert_eq!(capture.captured, Some(Piece::Pawn));
+
+        let board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion = board.parse_uci_move("a7a8n").unwrap();
+        assert_eq!((promotion.from, promotion.to), (at("a7"), at("a8")));
+        assert_eq!(promotion.promotion, Some(Piece::Knight));
+        assert_eq!(
+            board.parse_uci_move("a7a8").map(|mv| mv.promotion),
+            Err(ParseError::IllegalMove("a7a8".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_move_to_uci() {
+        let board = Board::new();
+        let push = board.parse_uci_move("g1f3").unwrap();
+        assert_eq!(board.move_to_uci(&push), "g1f3");
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let castle = board.parse_san("O-O").unwrap();
+        assert_eq!(board.move_to_uci(&castle), "e1g1");
+
+        let board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion = board.parse_san("a8=Q+").unwrap();
+        assert_eq!(board.move_to_uci(&promotion), "a7a8q");
+    }
+
+    #[test]
+    fn test_parse_uci_move_rejects_bad_text() {
+        let board = Board::new();
+        assert_eq!(
+            board.parse_uci_move("e2"),
+            Err(ParseError::BadUci("e2".to_string()))
+        );
+        assert_eq!(
+            board.parse_uci_move("e2e9"),
+            Err(ParseError::OutOfBounds("e9".to_string()))
+        );
+        assert_eq!(
+            board.parse_uci_move("e2e4x"),
+            Err(ParseError::BadUci("e2e4x".to_string()))
+        );
+        assert_eq!(
+            board.parse_uci_move("e2e5"),
+            Err(ParseError::IllegalMove("e2e5".to_string()))
+        );
+    }
+}
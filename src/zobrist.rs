@@ -0,0 +1,80 @@
+use std::sync::OnceLock;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{piece::Piece, CastlingRights, Color, Square};
+
+// Fixed seed so hashes are reproducible between runs
+const SEED: u64 = 0x5EED_C0FF_EE15_600D;
+
+pub(crate) struct ZobristKeys {
+    pieces: [[[u64; 64]; 6]; 2],
+    pub black_to_move: u64,
+    pub castling: [u64; 4],
+    pub en_passant_file: [u64; 8],
+}
+
+impl ZobristKeys {
+    fn generate() -> Self {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let mut pieces = [[[0; 64]; 6]; 2];
+        for color in pieces.iter_mut() {
+            for piece in color.iter_mut() {
+                for key in piece.iter_mut() {
+                    *key = rng.gen();
+                }
+            }
+        }
+        ZobristKeys {
+            pieces,
+            black_to_move: rng.gen(),
+            castling: rng.gen(),
+            en_passant_file: rng.gen(),
+        }
+    }
+
+    pub fn piece(&self, piece: Piece, color: Color, row: usize, col: usize) -> u64 {
+        let piece_index = match piece {
+            Piece::King => 0,
+            Piece::Queen => 1,
+            Piece::Rook => 2,
+            Piece::Bishop => 3,
+            Piece::Knight => 4,
+            Piece::Pawn => 5,
+            Piece::Empty => return 0,
+        };
+        let color_index = match color {
+            Color::White => 0,
+            Color::Black => 1,
+        };
+        self.pieces[color_index][piece_index][row * 8 + col]
+    }
+
+    /// Key for whatever stands on `square`, or 0 if it is empty.
+    pub fn square(&self, square: Square, row: usize, col: usize) -> u64 {
+        match square.color {
+            Some(color) => self.piece(square.piece, color, row, col),
+            None => 0,
+        }
+    }
+
+    /// Combined key for a set of castling rights.
+    pub fn castling_rights(&self, castling: CastlingRights) -> u64 {
+        let rights = [
+            castling.white_kingside,
+            castling.white_queenside,
+            castling.black_kingside,
+            castling.black_queenside,
+        ];
+        rights
+            .iter()
+            .zip(self.castling)
+            .filter(|(right, _)| **right)
+            .fold(0, |hash, (_, key)| hash ^ key)
+    }
+}
+
+pub(crate) fn keys() -> &'static ZobristKeys {
+    static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+    KEYS.get_or_init(ZobristKeys::generate)
+}
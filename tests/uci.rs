@@ -0,0 +1,53 @@
+use chess::uci::run_uci_with;
+
+fn run_script(script: &str) -> String {
+    let mut output = Vec::new();
+    run_uci_with(script.as_bytes(), &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_uci_handshake() {
+    let output = run_script("uci\nisready\nquit\n");
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines[0].starts_with("id name"));
+    assert!(lines.contains(&"uciok"));
+    assert_eq!(lines.last(), Some(&"readyok"));
+}
+
+#[test]
+fn test_uci_go_produces_bestmove() {
+    let output = run_script(
+        "uci\n\
+         isready\n\
+         position fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 moves e1d1 e8d8\n\
+         go\n\
+         quit\n",
+    );
+    let bestmove = output
+        .lines()
+        .find(|line| line.starts_with("bestmove "))
+        .expect("engine should answer go with bestmove");
+    let token = bestmove.trim_start_matches("bestmove ");
+    assert_eq!(token.len(), 4);
+    assert_ne!(token, "0000");
+}
+
+#[test]
+fn test_uci_go_reports_search_info() {
+    let output = run_script("position startpos\ngo\nquit\n");
+    let infos: Vec<&str> = output
+        .lines()
+        .filter(|line| line.starts_with("info "))
+        .collect();
+    assert!(!infos.is_empty());
+    assert!(infos[0].starts_with("info depth 1 score cp "));
+    assert!(infos.iter().all(|line| line.contains(" pv ")));
+    assert!(output.lines().last().unwrap().starts_with("bestmove "));
+}
+
+#[test]
+fn test_uci_stops_at_quit() {
+    let output = run_script("quit\nisready\n");
+    assert!(output.is_empty());
+}